@@ -0,0 +1,74 @@
+//! Count the allocations made translating colorful messages into fresh buffers.
+//!
+//! Run with `cargo bench --bench allocations`.
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::hint::black_box;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use ircat::Filter;
+use ircat::IrcatFilter;
+
+const MESSAGES: usize = 100_000;
+
+/// Count calls to `alloc` and `realloc`.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// SAFETY: Every call is forwarded to the system allocator unchanged.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::alloc`.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::dealloc`.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::realloc`.
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn count(name: &str, message: &[u8]) {
+    // A line of colorful art is many codes long.
+    let message = message.repeat(8);
+    let mut filter = IrcatFilter::init();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..MESSAGES {
+        let mut output = Vec::new();
+        filter.translate(black_box(&message), &mut output);
+        black_box(&output);
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    #[allow(clippy::cast_precision_loss)]
+    let per_message = allocations as f64 / MESSAGES as f64;
+    println!("{name}: {per_message:.2} allocations per message");
+}
+
+fn main() {
+    count(
+        "mixed",
+        b"<\x0304nick\x03> \x02hello\x02 \x0312,1world\x0f and some plain text",
+    );
+    count(
+        "colorful",
+        b"\x0304,01#\x0307,01#\x0308,01#\x0309,01#\x0311,01#\x0312,01#\x0306,01#\x0f",
+    );
+    count(
+        "hex",
+        b"\x04ff0000,000000#\x04ffff00,000000#\x0400ff00,000000#\x040000ff,000000#\x0f",
+    );
+}
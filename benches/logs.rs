@@ -0,0 +1,47 @@
+//! Time translating whole logs through the streaming API.
+//!
+//! Run with `cargo bench --bench logs`.
+
+use std::hint::black_box;
+use std::io::BufReader;
+use std::time::Instant;
+
+use ircat::ircat;
+
+/// Roughly how much log to generate for each run.
+const LOG_SIZE: usize = 16 << 20;
+
+fn log(line: &[u8]) -> Vec<u8> {
+    line.repeat(LOG_SIZE / line.len())
+}
+
+fn time(name: &str, log: &[u8]) {
+    let mut output = Vec::with_capacity(log.len() * 2);
+    let start = Instant::now();
+    for _ in 0..4 {
+        output.clear();
+        let _ = ircat(BufReader::new(black_box(log)), &mut output);
+        black_box(&output);
+    }
+    let elapsed = start.elapsed() / 4;
+    #[allow(clippy::cast_precision_loss)]
+    let throughput = log.len() as f64 / elapsed.as_secs_f64() / f64::from(1 << 20);
+    println!("{name}: {elapsed:?} per run, {throughput:.0} MiB/s");
+}
+
+fn main() {
+    time(
+        "plain",
+        &log(b"[12:34:56] <nick> a fairly long message without any formatting at all in it\n"),
+    );
+    time(
+        "mixed",
+        &log(
+            b"[12:34:56] <\x0304nick\x03> a message with \x02some\x02 formatting in \x0312it\x03\n",
+        ),
+    );
+    time(
+        "colorful",
+        &log(b"\x0304,01#\x0307,01#\x0308,01#\x0309,01#\x0311,01#\x0312,01#\x0306,01#\x0f\n"),
+    );
+}
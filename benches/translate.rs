@@ -0,0 +1,39 @@
+//! Time translating many short messages with and without reusing the output buffer.
+//!
+//! Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use ircat::ircat_bytes;
+use ircat::Filter;
+use ircat::IrcatFilter;
+
+const MESSAGES: usize = 1_000_000;
+
+fn time(name: &str, mut f: impl FnMut(&[u8])) {
+    let message = b"<\x0304nick\x03> \x02hello\x02 \x0312,1world\x0f and some plain text";
+    let start = Instant::now();
+    for _ in 0..MESSAGES {
+        f(black_box(message));
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name}: {elapsed:?} total, {:?} per message",
+        elapsed / u32::try_from(MESSAGES).unwrap_or(u32::MAX)
+    );
+}
+
+fn main() {
+    time("ircat_bytes", |message| {
+        black_box(ircat_bytes(message));
+    });
+
+    let mut filter = IrcatFilter::init();
+    let mut output = Vec::new();
+    time("translate", |message| {
+        output.clear();
+        filter.translate(message, &mut output);
+        black_box(&output);
+    });
+}
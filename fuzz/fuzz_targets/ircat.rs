@@ -0,0 +1,29 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &[u8]| {
+    let mut output = Vec::new();
+    ircat::ircat(input, &mut output).expect("writing to a Vec can't fail");
+
+    // Only ASCII bytes are added or removed so valid UTF-8 stays valid.
+    if std::str::from_utf8(input).is_ok() {
+        assert!(std::str::from_utf8(&output).is_ok(), "invalid UTF-8 from {input:?}");
+    }
+
+    // Without escapes in the input every escape in the output is an SGR sequence.
+    if !input.contains(&b'\x1b') {
+        let mut rest = output.as_slice();
+        while let Some(start) = rest.iter().position(|c| *c == b'\x1b') {
+            let sequence = rest[start + 1..]
+                .strip_prefix(b"[")
+                .expect("an escape starts a control sequence");
+            let len = sequence
+                .iter()
+                .position(|c| !matches!(c, b'0'..=b'9' | b';'))
+                .expect("the sequence is terminated");
+            assert_eq!(sequence[len], b'm', "not an SGR sequence in {output:?}");
+            rest = &sequence[len + 1..];
+        }
+    }
+});
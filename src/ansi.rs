@@ -0,0 +1,456 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
+
+use crate::color::lookup_rgb;
+use crate::color::nearest_ansi16;
+use crate::color::nearest_xterm256;
+use crate::color::write_sgr;
+use crate::color::xterm_rgb;
+use crate::color::ColorMode;
+use crate::color::Palette;
+use crate::parser::Attribute;
+use crate::parser::Event;
+use crate::write::VecWriter;
+
+impl Attribute {
+    fn on(self) -> &'static [u8] {
+        match self {
+            Self::Bold => b"\x1b[1m",
+            Self::Italic => b"\x1b[3m",
+            Self::Underline => b"\x1b[4m",
+            Self::Strikethrough => b"\x1b[9m",
+            Self::Reverse => b"\x1b[7m",
+            Self::Blink => b"\x1b[5m",
+        }
+    }
+
+    fn off(self) -> &'static [u8] {
+        match self {
+            Self::Bold => b"\x1b[22m",
+            Self::Italic => b"\x1b[23m",
+            Self::Underline => b"\x1b[24m",
+            Self::Strikethrough => b"\x1b[29m",
+            Self::Reverse => b"\x1b[27m",
+            Self::Blink => b"\x1b[25m",
+        }
+    }
+}
+
+/// Faint text for [`AnsiOptions::dim`].
+const DIM: &[u8] = b"\x1b[2m";
+
+/// Renders parsed IRC formatting as ANSI escape sequences.
+#[derive(Debug)]
+pub(crate) struct Ansi {
+    palette: Palette,
+    color_mode: ColorMode,
+    options: AnsiOptions,
+    /// Whether nothing has been rendered on the current line yet.
+    line_start: bool,
+    /// The last foreground color emitted or `None` if none has been.
+    fg_color: Option<Color>,
+    /// The last background color emitted or `None` if none has been.
+    bg_color: Option<Color>,
+    attributes: u8,
+    /// The delimiters around every sequence written, marking them as taking no space.
+    prompt_escapes: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Switches for the ANSI output.
+#[derive(Clone, Copy, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub(crate) struct AnsiOptions {
+    /// Whether IRC colors 8–15 use the bright ANSI colors.
+    pub(crate) bright: bool,
+    /// Whether every line starts with a reset.
+    pub(crate) reset_lines: bool,
+    /// Whether resetting both colors uses a single sequence.
+    pub(crate) combine_resets: bool,
+    /// Whether every line is faint.
+    pub(crate) dim: bool,
+    /// Whether the standard colors use the same `38;5;N` form as the other colors.
+    pub(crate) uniform_colors: bool,
+    /// Whether hex colors use the nearest color of the color mode rather than 24-bit colors.
+    pub(crate) quantize_hex: bool,
+    /// Whether bold text uses the bright variant of a standard foreground color.
+    pub(crate) bold_bright: bool,
+    /// Whether IRC color 1 is the terminal's black as a foreground whatever the palette says.
+    pub(crate) true_black_fg: bool,
+    /// Whether IRC color 1 is the terminal's black as a background whatever the palette says.
+    pub(crate) true_black_bg: bool,
+    /// The IRC color to reset the foreground to instead of the terminal default.
+    pub(crate) default_fg: Option<u8>,
+    /// The IRC color to reset the background to instead of the terminal default.
+    pub(crate) default_bg: Option<u8>,
+}
+
+/// A color as last emitted so repeated codes for it can be skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    Default,
+    Irc(u8),
+    Rgb(u32),
+    Xterm(u8),
+}
+
+impl Color {
+    fn irc(color: u8) -> Self {
+        if color == 99 {
+            Self::Default
+        } else {
+            Self::Irc(color)
+        }
+    }
+
+    /// Whether `color` leaves anything other than the default color active.
+    fn is_set(color: Option<Self>) -> bool {
+        matches!(color, Some(Self::Irc(_) | Self::Rgb(_) | Self::Xterm(_)))
+    }
+}
+
+impl Ansi {
+    pub(crate) fn new(palette: Palette, color_mode: ColorMode, options: AnsiOptions) -> Self {
+        Self {
+            palette,
+            color_mode: color_mode.resolve(),
+            options,
+            line_start: true,
+            fg_color: None,
+            bg_color: None,
+            attributes: 0,
+            prompt_escapes: None,
+        }
+    }
+
+    pub(crate) fn with_prompt_escapes(
+        mut self,
+        prompt_escapes: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> Self {
+        self.prompt_escapes = prompt_escapes;
+        self
+    }
+
+    pub(crate) fn render(&mut self, event: Event<'_>, output: &mut Vec<u8>) {
+        let start = output.len();
+        if self.line_start && !matches!(event, Event::LineEnd(_)) {
+            self.line_start = false;
+            if self.options.reset_lines {
+                self.reset_all(output);
+            } else if self.options.dim {
+                output.extend_from_slice(DIM);
+            }
+        }
+
+        match event {
+            Event::Text(text) => {
+                self.escape(output, start);
+                output.extend_from_slice(text);
+                return;
+            }
+            Event::LineEnd(c) => {
+                self.end_line(output);
+                self.escape(output, start);
+                output.push(c);
+                self.line_start = true;
+                return;
+            }
+            Event::Toggle(attribute) => {
+                self.attributes ^= attribute.mask();
+                if self.attributes & attribute.mask() == 0 {
+                    output.extend_from_slice(attribute.off());
+                    // Turning bold off turns faint off too.
+                    if self.options.dim && matches!(attribute, Attribute::Bold) {
+                        output.extend_from_slice(DIM);
+                    }
+                } else {
+                    output.extend_from_slice(attribute.on());
+                }
+                // Switch between the standard and bright variant of the foreground.
+                if self.options.bold_bright && matches!(attribute, Attribute::Bold) {
+                    if let Some(Color::Irc(color)) = self.fg_color {
+                        if self.standard(color).is_some() {
+                            self.output_color(output, true, color);
+                        }
+                    }
+                }
+            }
+            // There is no ANSI equivalent for monospace so drop it.
+            Event::Monospace => {}
+            Event::Color(fg_color, bg_color) => {
+                self.set_color(output, true, Color::irc(fg_color));
+                if let Some(bg_color) = bg_color {
+                    self.set_color(output, false, Color::irc(bg_color));
+                }
+            }
+            Event::HexColor(fg_color, bg_color) => {
+                self.set_color(output, true, Color::Rgb(fg_color));
+                if let Some(bg_color) = bg_color {
+                    self.set_color(output, false, Color::Rgb(bg_color));
+                }
+            }
+            Event::Background(bg_color) => self.set_color(output, false, Color::irc(bg_color)),
+            Event::HexBackground(bg_color) => self.set_color(output, false, Color::Rgb(bg_color)),
+            Event::XtermColor(fg_color, bg_color) => {
+                self.set_color(output, true, Color::Xterm(fg_color));
+                if let Some(bg_color) = bg_color {
+                    self.set_color(output, false, Color::Xterm(bg_color));
+                }
+            }
+            Event::XtermBackground(bg_color) => {
+                self.set_color(output, false, Color::Xterm(bg_color));
+            }
+            Event::ResetColor => self.reset_color(output),
+            Event::Unknown => {}
+            Event::Ansi(sequence) => output.extend_from_slice(sequence),
+            Event::Reset => {
+                if self.is_active() {
+                    self.reset_all(output);
+                }
+            }
+        }
+        self.escape(output, start);
+    }
+
+    /// Put the prompt escapes around the sequences written since `start`, if there are any.
+    fn escape(&self, output: &mut Vec<u8>, start: usize) {
+        if let Some((open, close)) = &self.prompt_escapes {
+            if output.len() != start {
+                output.splice(start..start, open.iter().copied());
+                output.extend_from_slice(close);
+            }
+        }
+    }
+
+    /// Reset all formatting then switch to the configured default colors.
+    fn reset_all(&mut self, output: &mut Vec<u8>) {
+        output.extend_from_slice(b"\x1b[0m");
+        if self.options.dim {
+            output.extend_from_slice(DIM);
+        }
+        self.fg_color = None;
+        self.bg_color = None;
+        self.attributes = 0;
+        if self.options.default_fg.is_some() {
+            self.set_color(output, true, Color::Default);
+        }
+        if self.options.default_bg.is_some() {
+            self.set_color(output, false, Color::Default);
+        }
+    }
+
+    /// Switch to `color` unless it is already active.
+    fn set_color(&mut self, output: &mut Vec<u8>, foreground: bool, color: Color) {
+        let current = if foreground {
+            &mut self.fg_color
+        } else {
+            &mut self.bg_color
+        };
+        if *current == Some(color) {
+            return;
+        }
+        *current = Some(color);
+        match color {
+            Color::Default => {
+                let default = if foreground {
+                    self.options.default_fg
+                } else {
+                    self.options.default_bg
+                };
+                match default {
+                    Some(color) => self.output_color(output, foreground, color),
+                    None => {
+                        output.extend_from_slice(if foreground {
+                            b"\x1b[39m"
+                        } else {
+                            b"\x1b[49m"
+                        });
+                    }
+                }
+            }
+            Color::Irc(color) => self.output_color(output, foreground, color),
+            Color::Rgb(rgb) => self.output_hex(output, foreground, rgb),
+            Color::Xterm(index) => self.output_xterm(output, foreground, index),
+        }
+    }
+
+    fn output_xterm(&self, output: &mut Vec<u8>, foreground: bool, index: u8) {
+        // Writing to a Vec can't fail.
+        let _ = match self.color_mode {
+            ColorMode::Ansi16 => {
+                self.output_ansi16(output, foreground, nearest_ansi16(xterm_rgb(index)))
+            }
+            _ => write!(
+                VecWriter(output),
+                "\x1b[{};5;{index}m",
+                if foreground { 38 } else { 48 }
+            ),
+        };
+    }
+
+    fn output_hex(&self, output: &mut Vec<u8>, foreground: bool, rgb: u32) {
+        let base = if foreground { 30 } else { 40 };
+        // Writing to a Vec can't fail.
+        let _ = match self.color_mode {
+            ColorMode::Ansi16 if self.options.quantize_hex => {
+                self.output_ansi16(output, foreground, nearest_ansi16(rgb))
+            }
+            ColorMode::Ansi256 if self.options.quantize_hex => write!(
+                VecWriter(output),
+                "\x1b[{};5;{}m",
+                base + 8,
+                nearest_xterm256(rgb)
+            ),
+            _ => {
+                output_rgb(output, foreground, rgb);
+                Ok(())
+            }
+        };
+    }
+
+    fn output_color(&self, output: &mut Vec<u8>, foreground: bool, color: u8) {
+        let base = if foreground { 30 } else { 40 };
+        let bold = self.attributes & Attribute::Bold.mask() != 0;
+        if foreground && bold && self.options.bold_bright {
+            if let Some(index) = self.standard(color) {
+                // Writing to a Vec can't fail.
+                let _ = self.output_ansi16(output, foreground, index + 8);
+                return;
+            }
+        }
+        // Writing to a Vec can't fail.
+        let _ = match (self.color_mode, lookup_rgb(color)) {
+            (ColorMode::Ansi16 | ColorMode::Ansi256, Some(rgb))
+                if self.options.bright && (8..=15).contains(&color) =>
+            {
+                self.output_ansi16(output, foreground, 8 + nearest_ansi16(rgb) % 8)
+            }
+            (ColorMode::Ansi16, Some(rgb)) => {
+                self.output_ansi16(output, foreground, nearest_ansi16(rgb))
+            }
+            (ColorMode::TrueColor, Some(rgb)) => {
+                output_rgb(output, foreground, rgb);
+                Ok(())
+            }
+            (ColorMode::Ansi256, _) if self.true_black(foreground, color) => {
+                self.output_ansi16(output, foreground, 0)
+            }
+            // Unknown colors fall back to the default color (39 or 49).
+            _ => match self.palette.get(color).unwrap_or("9") {
+                parameter @ ("0" | "1" | "2" | "3" | "4" | "5" | "6" | "7")
+                    if self.options.uniform_colors =>
+                {
+                    write!(VecWriter(output), "\x1b[{}8;5;{parameter}m", base / 10)
+                }
+                parameter => write_sgr(&mut VecWriter(output), foreground, parameter),
+            },
+        };
+    }
+
+    /// The xterm standard color, as opposed to a bright one, IRC color `color` is written as.
+    fn standard(&self, color: u8) -> Option<u8> {
+        let index = match (self.color_mode, lookup_rgb(color)) {
+            (ColorMode::Ansi16 | ColorMode::Ansi256, Some(_))
+                if self.options.bright && (8..=15).contains(&color) =>
+            {
+                return None
+            }
+            (ColorMode::Ansi16, Some(rgb)) => nearest_ansi16(rgb),
+            (ColorMode::TrueColor, Some(_)) => return None,
+            (ColorMode::Ansi256, _) if self.true_black(true, color) => 0,
+            _ => match self.palette.get(color)?.as_bytes() {
+                [index] => index.wrapping_sub(b'0'),
+                _ => return None,
+            },
+        };
+        (index < 8).then_some(index)
+    }
+
+    /// Whether IRC color `color` is forced to the terminal's black.
+    fn true_black(&self, foreground: bool, color: u8) -> bool {
+        color == 1
+            && if foreground {
+                self.options.true_black_fg
+            } else {
+                self.options.true_black_bg
+            }
+    }
+
+    /// Write xterm standard color `index`.
+    fn output_ansi16(&self, output: &mut Vec<u8>, foreground: bool, index: u8) -> fmt::Result {
+        let base = if foreground { 30 } else { 40 };
+        if self.options.uniform_colors {
+            write!(VecWriter(output), "\x1b[{};5;{index}m", base + 8)
+        } else if index < 8 {
+            write!(VecWriter(output), "\x1b[{}m", base + index)
+        } else {
+            write!(VecWriter(output), "\x1b[{}m", base + 60 + index - 8)
+        }
+    }
+
+    fn reset_color(&mut self, output: &mut Vec<u8>) {
+        if self.options.combine_resets
+            && self.options.default_fg.is_none()
+            && self.options.default_bg.is_none()
+            && Color::is_set(self.fg_color)
+            && Color::is_set(self.bg_color)
+        {
+            output.extend_from_slice(b"\x1b[39;49m");
+            self.fg_color = Some(Color::Default);
+            self.bg_color = Some(Color::Default);
+            return;
+        }
+        if Color::is_set(self.fg_color) {
+            self.set_color(output, true, Color::Default);
+        }
+        if Color::is_set(self.bg_color) {
+            self.set_color(output, false, Color::Default);
+        }
+    }
+
+    /// Forget all formatting without emitting anything.
+    pub(crate) fn reset(&mut self) {
+        self.line_start = true;
+        self.fg_color = None;
+        self.bg_color = None;
+        self.attributes = 0;
+    }
+
+    /// Whether any formatting is active.
+    pub(crate) fn is_active(&self) -> bool {
+        Color::is_set(self.fg_color) || Color::is_set(self.bg_color) || self.attributes != 0
+    }
+
+    pub(crate) fn finish(&mut self, output: &mut Vec<u8>) {
+        let start = output.len();
+        self.end_line(output);
+        self.escape(output, start);
+        self.line_start = true;
+    }
+
+    fn end_line(&mut self, output: &mut Vec<u8>) {
+        self.reset_color(output);
+        for attribute in Attribute::ALL {
+            if self.attributes & attribute.mask() != 0 {
+                output.extend_from_slice(attribute.off());
+            }
+        }
+        // Turning bold off already turned faint off.
+        if self.options.dim && !self.line_start && self.attributes & Attribute::Bold.mask() == 0 {
+            output.extend_from_slice(Attribute::Bold.off());
+        }
+        self.attributes = 0;
+    }
+}
+
+fn output_rgb(output: &mut Vec<u8>, foreground: bool, rgb: u32) {
+    // Writing to a Vec can't fail.
+    let _ = write!(
+        VecWriter(output),
+        "\x1b[{};2;{};{};{}m",
+        if foreground { 38 } else { 48 },
+        rgb >> 16,
+        (rgb >> 8) & 0xff,
+        rgb & 0xff,
+    );
+}
@@ -0,0 +1,557 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+#[cfg(feature = "std")]
+use std::io::Result;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+use crate::ansi::Ansi;
+use crate::ansi::AnsiOptions;
+use crate::color::ColorMode;
+use crate::color::Palette;
+use crate::columns::Columns;
+use crate::ctcp::Ctcp;
+#[cfg(feature = "std")]
+use crate::filter::BufFilter;
+use crate::filter::Filter;
+use crate::highlight::Highlighter;
+use crate::html::Html;
+use crate::hyperlink::Hyperlinks;
+use crate::irc::Irc;
+use crate::pango::Pango;
+use crate::parser::Attribute;
+use crate::parser::Bell;
+use crate::parser::Parser;
+use crate::stats::Stats;
+use crate::tabs::Tabs;
+use crate::timestamp::Timestamps;
+use crate::weechat::WeeChatParser;
+use crate::wrap::Wrap;
+use crate::Decoder;
+use crate::IrcatFilter;
+use crate::Renderer;
+use crate::Stages;
+
+/// The formatting syntax of the input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InputFormat {
+    /// mIRC style formatting codes.
+    #[default]
+    Irc,
+    /// WeeChat's internal formatting codes as found in some of its logs.
+    ///
+    /// Only a subset is supported:
+    ///
+    /// - `\x19F` or `\x19B` followed by attributes and a color sets the foreground or
+    ///   background.
+    /// - `\x19*` followed by attributes, a color, and optionally `,` or `~` and a second color
+    ///   sets the foreground and background.
+    /// - `\x19\x1c` resets the colors.
+    /// - `\x1a` or `\x1b` followed by an attribute sets or removes it.
+    /// - `\x1c` resets all formatting.
+    ///
+    /// Attributes are `*` bold, `!` reverse, `/` italic, `_` underline, and `|` which is
+    /// accepted but ignored. Colors are either two digits naming one of WeeChat's basic colors
+    /// (`00` to `16`) or `@` and five digits naming an extended color (`00000` to `00255`).
+    /// Extended colors are xterm 256 color indices and are written as such, or as the nearest
+    /// standard color with [`ColorMode::Ansi16`].
+    ///
+    /// Any other sequence starting with `\x19`, `\x1a`, or `\x1b` is printed literally
+    /// without that byte.
+    WeeChat,
+}
+
+/// The format to translate IRC formatting into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// ANSI escape sequences.
+    #[default]
+    Ansi,
+    /// HTML `<span>` elements with inline styles.
+    Html,
+    /// IRC formatting codes normalized to their shortest form.
+    ///
+    /// This renders the same as the input but drops codes without effect, such as resets of
+    /// formatting that is not set, and writes each color with as few digits as possible without
+    /// running into the text that follows.
+    Irc,
+    /// Pango markup as used by GTK and desktop notifications.
+    Pango,
+    /// Plain text with all formatting removed.
+    Strip,
+}
+
+/// Configures how IRC formatting is translated.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::ColorMode;
+/// # use ircat::Filter;
+/// # use ircat::IrcatBuilder;
+/// #
+/// let mut filter = IrcatBuilder::default().color_mode(ColorMode::TrueColor).build();
+/// let mut output = Vec::new();
+/// filter.filter(b"\x034red\n", &mut output);
+/// filter.finish(&mut output);
+/// assert_eq!(output, b"\x1b[38;2;255;0;0mred\x1b[39m\n");
+/// ```
+#[derive(Clone, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct IrcatBuilder {
+    palette: Palette,
+    color_mode: ColorMode,
+    ansi_options: AnsiOptions,
+    ansi_passthrough: bool,
+    prompt_escapes: Option<(Vec<u8>, Vec<u8>)>,
+    reset_byte: Option<u8>,
+    color_byte: Option<u8>,
+    blink_byte: Option<u8>,
+    bell: Bell,
+    disabled_attributes: u8,
+    ctcp: bool,
+    hyperlinks: bool,
+    highlighter: Option<Highlighter>,
+    timestamps: Option<Timestamps>,
+    strip_controls: bool,
+    tab_width: usize,
+    wrap_width: usize,
+    track_columns: bool,
+    input: InputFormat,
+    output: OutputFormat,
+}
+
+impl IrcatBuilder {
+    /// Map IRC colors to ANSI colors using `palette` when the color mode is
+    /// [`ColorMode::Ansi256`].
+    #[must_use]
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Choose the kind of ANSI color sequences to emit.
+    #[must_use]
+    pub fn color_mode(mut self, color_mode: ColorMode) -> Self {
+        self.color_mode = color_mode;
+        self
+    }
+
+    /// Render IRC colors 8–15 with the bright ANSI colors (90–97 and 100–107) instead of the
+    /// palette when the color mode is not [`ColorMode::TrueColor`].
+    #[must_use]
+    pub fn bright(mut self, bright: bool) -> Self {
+        self.ansi_options.bright = bright;
+        self
+    }
+
+    /// Write every color as `\x1b[38;5;Nm` or `\x1b[48;5;Nm` when the color mode is not
+    /// [`ColorMode::TrueColor`], including the standard colors which are otherwise written as
+    /// `\x1b[31m` and the like.
+    ///
+    /// This makes output easier to parse at the cost of a few bytes per color. The default colors
+    /// are still reset with `\x1b[39m` and `\x1b[49m`.
+    #[must_use]
+    pub fn uniform_colors(mut self, uniform_colors: bool) -> Self {
+        self.ansi_options.uniform_colors = uniform_colors;
+        self
+    }
+
+    /// Render standard foreground colors as their bright variants (`\x1b[91m` rather than
+    /// `\x1b[31m`) while bold is on, like terminals that brighten bold text do.
+    ///
+    /// Colors from the 256-color palette and 24-bit colors are left alone.
+    #[must_use]
+    pub fn bold_bright(mut self, bold_bright: bool) -> Self {
+        self.ansi_options.bold_bright = bold_bright;
+        self
+    }
+
+    /// Render IRC color 1 as the terminal's black (`\x1b[30m`) instead of the palette's color
+    /// when it is a foreground and the color mode is [`ColorMode::Ansi256`].
+    ///
+    /// Unlike [`Palette::set_true_black`] this leaves backgrounds alone so dark gray backgrounds
+    /// can be kept with black text, see [`true_black_bg`](Self::true_black_bg).
+    #[must_use]
+    pub fn true_black_fg(mut self, true_black: bool) -> Self {
+        self.ansi_options.true_black_fg = true_black;
+        self
+    }
+
+    /// Render IRC color 1 as the terminal's black (`\x1b[40m`) instead of the palette's color
+    /// when it is a background and the color mode is [`ColorMode::Ansi256`].
+    ///
+    /// Unlike [`Palette::set_true_black`] this leaves foregrounds alone, see
+    /// [`true_black_fg`](Self::true_black_fg).
+    #[must_use]
+    pub fn true_black_bg(mut self, true_black: bool) -> Self {
+        self.ansi_options.true_black_bg = true_black;
+        self
+    }
+
+    /// Render hex colors with the closest color of the color mode instead of 24-bit colors when
+    /// the color mode is not [`ColorMode::TrueColor`].
+    ///
+    /// [`ColorMode::Ansi256`] picks from the 6×6×6 color cube and grayscale ramp, leaving out the
+    /// standard colors that terminal themes often change.
+    #[must_use]
+    pub fn quantize_hex(mut self, quantize_hex: bool) -> Self {
+        self.ansi_options.quantize_hex = quantize_hex;
+        self
+    }
+
+    /// Start every line of ANSI output with a full reset.
+    ///
+    /// This guards against formatting left active by whatever was written before, such as an
+    /// earlier line that was cut off, at the cost of a few bytes per line.
+    #[must_use]
+    pub fn reset_lines(mut self, reset_lines: bool) -> Self {
+        self.ansi_options.reset_lines = reset_lines;
+        self
+    }
+
+    /// Render every line of ANSI output faint, such as for server notices.
+    ///
+    /// The IRC formatting of the line still applies on top, and faint is turned back on after
+    /// anything in the line that turns it off such as `\x0f`.
+    #[must_use]
+    pub fn dim(mut self, dim: bool) -> Self {
+        self.ansi_options.dim = dim;
+        self
+    }
+
+    /// Reset both colors with a single `\x1b[39;49m` instead of `\x1b[39m\x1b[49m`.
+    #[must_use]
+    pub fn combine_resets(mut self, combine_resets: bool) -> Self {
+        self.ansi_options.combine_resets = combine_resets;
+        self
+    }
+
+    /// Recognize ANSI control sequences such as `\x1b[1m` already in the input.
+    ///
+    /// Complete sequences are passed through verbatim to ANSI output and removed from other
+    /// output formats. Incomplete sequences are dropped so the codes that follow are not
+    /// swallowed by them, as are sequences longer than 64 bytes. This only applies to
+    /// [`InputFormat::Irc`].
+    #[must_use]
+    pub fn ansi_passthrough(mut self, ansi_passthrough: bool) -> Self {
+        self.ansi_passthrough = ansi_passthrough;
+        self
+    }
+
+    /// Make `http` and `https` URLs in the text clickable with OSC 8 hyperlinks.
+    ///
+    /// A URL runs until a formatting code or a byte that is not printable ASCII, leaving out any
+    /// trailing punctuation, and keeps the formatting around it. URLs longer than 4096 bytes are
+    /// left as text. Only ANSI output shows the links, other output formats are unchanged.
+    #[must_use]
+    pub fn hyperlinks(mut self, hyperlinks: bool) -> Self {
+        self.hyperlinks = hyperlinks;
+        self
+    }
+
+    /// Translate the code toggling `attribute` if `enable`, the default, or drop it otherwise.
+    ///
+    /// Dropping suits terminals that render an attribute badly, such as italics shown as reversed
+    /// colors. The text inside is kept as is.
+    #[must_use]
+    pub fn enable(mut self, attribute: Attribute, enable: bool) -> Self {
+        if enable {
+            self.disabled_attributes &= !attribute.mask();
+        } else {
+            self.disabled_attributes |= attribute.mask();
+        }
+        self
+    }
+
+    /// Start every line with the timestamp `timestamp` appends to the buffer it is given.
+    ///
+    /// It is called as each line starts, before any of its formatting so the timestamp shows in
+    /// the default colors. Lines end at `\n`, so lines broken by [`wrap`](Self::wrap) continue
+    /// without a timestamp and count it towards their width.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use std::time::SystemTime;
+    /// #
+    /// # use ircat::Filter;
+    /// # use ircat::IrcatBuilder;
+    /// #
+    /// let builder = IrcatBuilder::default().timestamp(|output| {
+    ///     let seconds = SystemTime::now()
+    ///         .duration_since(SystemTime::UNIX_EPOCH)
+    ///         .map_or(0, |now| now.as_secs());
+    ///     let (hours, minutes) = (seconds / 3600 % 24, seconds / 60 % 60);
+    ///     let _ = write!(output, "{hours:02}:{minutes:02}:{:02} ", seconds % 60);
+    /// });
+    /// let mut filter = builder.build();
+    /// let mut output = Vec::new();
+    /// filter.filter(b"\x034red\n", &mut output);
+    /// filter.finish(&mut output);
+    /// assert_eq!(output[2], b':');
+    /// assert!(output.ends_with(b" \x1b[31mred\x1b[39m\n"));
+    /// ```
+    #[must_use]
+    pub fn timestamp(mut self, timestamp: impl Fn(&mut Vec<u8>) + Send + Sync + 'static) -> Self {
+        self.timestamps = Some(Timestamps::new(timestamp));
+        self
+    }
+
+    /// Surround every escape sequence written with `open` and `close` so a shell prompt does not
+    /// count them towards its length, such as `\[` and `\]` for bash or `%{` and `%}` for zsh.
+    ///
+    /// Sequences passed through or added by other options are surrounded too. This only applies to
+    /// [`OutputFormat::Ansi`].
+    #[must_use]
+    pub fn prompt_escapes(mut self, open: &str, close: &str) -> Self {
+        self.prompt_escapes = Some((open.as_bytes().to_vec(), close.as_bytes().to_vec()));
+        self
+    }
+
+    /// Reset all formatting at `byte` as well as at `\x0f`, for logs that use another byte for it.
+    ///
+    /// A byte that already has a meaning, such as another formatting code or a digit inside a
+    /// color code, keeps it. This only applies to [`InputFormat::Irc`].
+    #[must_use]
+    pub fn reset_byte(mut self, byte: u8) -> Self {
+        self.reset_byte = Some(byte);
+        self
+    }
+
+    /// Start a color code at `byte` as well as at `\x03`, for bridges that use another byte for it.
+    ///
+    /// The digits after it follow the same rules as after `\x03`. A byte that already has a
+    /// meaning keeps it. This only applies to [`InputFormat::Irc`].
+    #[must_use]
+    pub fn color_byte(mut self, byte: u8) -> Self {
+        self.color_byte = Some(byte);
+        self
+    }
+
+    /// Toggle blinking text at `byte`, for logs from old clients that used a byte for it.
+    ///
+    /// Blink is turned off at the end of the line and by a reset like the other attributes. Only
+    /// ANSI output shows it. A byte that already has a meaning keeps it. This only applies to
+    /// [`InputFormat::Irc`].
+    #[must_use]
+    pub fn blink_byte(mut self, byte: u8) -> Self {
+        self.blink_byte = Some(byte);
+        self
+    }
+
+    /// Choose what to do with the bell byte (`\x07`) so catting a log does not beep.
+    ///
+    /// Unlike [`strip_controls`](Self::strip_controls) this leaves every other byte alone and can
+    /// show where the bell was. This only applies to [`InputFormat::Irc`].
+    #[must_use]
+    pub fn bell(mut self, bell: Bell) -> Self {
+        self.bell = bell;
+        self
+    }
+
+    /// Highlight each of `nicks` in the text by turning on the SGR parameters `on` before it and
+    /// `off` after it, such as `7` and `27` for reverse video.
+    ///
+    /// Nicks match whole words ignoring ASCII case. Formatting codes end a word so a nick right
+    /// next to a color code still matches. Only ANSI and IRC output show the highlight, other
+    /// output formats are unchanged.
+    #[must_use]
+    pub fn highlight<S: AsRef<str>>(
+        mut self,
+        nicks: impl IntoIterator<Item = S>,
+        on: &str,
+        off: &str,
+    ) -> Self {
+        let nicks: Vec<_> = nicks
+            .into_iter()
+            .map(|nick| nick.as_ref().as_bytes().to_vec())
+            .filter(|nick| !nick.is_empty())
+            .collect();
+        self.highlighter = (!nicks.is_empty()).then(|| Highlighter::new(nicks, on, off));
+        self
+    }
+
+    /// Render CTCP ACTION messages, as sent by `/me`, as `* text` and remove the `\x01` around
+    /// other CTCP messages.
+    ///
+    /// An action has to start its line. The formatting inside any CTCP message is translated as
+    /// usual.
+    #[must_use]
+    pub fn ctcp(mut self, ctcp: bool) -> Self {
+        self.ctcp = ctcp;
+        self
+    }
+
+    /// Remove control bytes that are not formatting codes, tabs, or line endings.
+    ///
+    /// This keeps stray bytes such as the `\x01` around CTCP messages or `\x07` from reaching a
+    /// terminal where they would show as garbage or ring the bell. Sequences kept by
+    /// [`ansi_passthrough`](Self::ansi_passthrough) are not removed.
+    #[must_use]
+    pub fn strip_controls(mut self, strip_controls: bool) -> Self {
+        self.strip_controls = strip_controls;
+        self
+    }
+
+    /// Expand tabs to spaces up to the next multiple of `width` columns.
+    ///
+    /// Columns count the display width of the text, not formatting, so colored columns line up.
+    /// Wide characters take two columns and combining marks none. Tab stops count from the start
+    /// of each line, including lines broken by [`wrap`](Self::wrap). A `width` of zero, the
+    /// default, leaves tabs alone.
+    #[must_use]
+    pub fn expand_tabs(mut self, width: usize) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    /// Break lines after `width` columns, continuing the formatting on the next line.
+    ///
+    /// Columns count the display width of the text, not formatting, and tabs count as one column
+    /// unless [expanded](Self::expand_tabs). A wide character that does not fit moves to the next
+    /// line. A `width` of zero, the default, leaves lines alone.
+    #[must_use]
+    pub fn wrap(mut self, width: usize) -> Self {
+        self.wrap_width = width;
+        self
+    }
+
+    /// Count the display columns of the output so they can be read back with
+    /// [`IrcatFilter::column`] and [`IrcatFilter::line_widths`], such as to pad colored tables.
+    ///
+    /// The widths of the lines are kept until they are taken.
+    #[must_use]
+    pub fn track_columns(mut self, track_columns: bool) -> Self {
+        self.track_columns = track_columns;
+        self
+    }
+
+    /// Reset the foreground to IRC color `color` instead of the terminal's default.
+    ///
+    /// This is also used for IRC color 99.
+    #[must_use]
+    pub fn default_fg(mut self, color: u8) -> Self {
+        self.ansi_options.default_fg = Some(color);
+        self
+    }
+
+    /// Reset the background to IRC color `color` instead of the terminal's default.
+    ///
+    /// This is also used for IRC color 99.
+    #[must_use]
+    pub fn default_bg(mut self, color: u8) -> Self {
+        self.ansi_options.default_bg = Some(color);
+        self
+    }
+
+    /// Choose the formatting syntax of the input.
+    #[must_use]
+    pub fn input(mut self, input: InputFormat) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Choose the format to translate into.
+    #[must_use]
+    pub fn output(mut self, output: OutputFormat) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Get the bytes written at the end of a line after the formatting codes in `formatting`,
+    /// such as the resets the ANSI output puts before the line ending.
+    ///
+    /// These come from a filter with this configuration so they follow every option, like
+    /// [`combine_resets`](Self::combine_resets) or [`default_fg`](Self::default_fg), and only
+    /// end what `formatting` leaves set. The line ending itself is left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ircat::IrcatBuilder;
+    /// #
+    /// let builder = IrcatBuilder::default();
+    /// assert_eq!(builder.reset_sequence(b"\x034,2"), b"\x1b[39m\x1b[49m");
+    /// assert_eq!(builder.reset_sequence(b"\x034"), b"\x1b[39m");
+    /// let builder = IrcatBuilder::default().combine_resets(true);
+    /// assert_eq!(builder.reset_sequence(b"\x034,2"), b"\x1b[39;49m");
+    /// ```
+    #[must_use]
+    pub fn reset_sequence(&self, formatting: &[u8]) -> Vec<u8> {
+        let mut filter = self.build();
+        let mut output = Vec::new();
+        filter.filter(formatting, &mut output);
+        // End any code still open so it is not taken as part of the line ending.
+        filter.decoder.finish(|event| {
+            filter
+                .stages
+                .render(event, &mut filter.renderer, &mut output);
+        });
+        output.clear();
+        filter.filter(b"\n", &mut output);
+        output.pop();
+        output
+    }
+
+    /// Create a filter with this configuration.
+    #[must_use]
+    pub fn build(&self) -> IrcatFilter {
+        IrcatFilter {
+            decoder: match self.input {
+                InputFormat::Irc => Decoder::Irc(
+                    Parser::new(self.ansi_passthrough)
+                        .with_reset_byte(self.reset_byte)
+                        .with_color_byte(self.color_byte)
+                        .with_blink_byte(self.blink_byte)
+                        .with_bell(self.bell),
+                ),
+                InputFormat::WeeChat => Decoder::WeeChat(WeeChatParser::new()),
+            },
+            stages: Stages {
+                disabled: self.disabled_attributes,
+                ctcp: self.ctcp.then(Ctcp::new),
+                strip_controls: self.strip_controls,
+                // Terminal escapes have no place in the other formats.
+                hyperlinks: (self.hyperlinks && self.output == OutputFormat::Ansi)
+                    .then(Hyperlinks::new),
+                highlighter: self.highlighter.clone(),
+                timestamps: self.timestamps.clone(),
+                wrap: (self.wrap_width != 0).then(|| Wrap::new(self.wrap_width, self.tab_width)),
+                tabs: (self.tab_width != 0).then(|| Tabs::new(self.tab_width)),
+                columns: self.track_columns.then(Columns::default),
+            },
+            stats: Stats::default(),
+            renderer: match self.output {
+                OutputFormat::Ansi => Renderer::Ansi(Box::new(
+                    Ansi::new(self.palette.clone(), self.color_mode, self.ansi_options)
+                        .with_prompt_escapes(self.prompt_escapes.clone()),
+                )),
+                OutputFormat::Html => Renderer::Html(Html::new()),
+                OutputFormat::Irc => Renderer::Irc(Irc::new()),
+                OutputFormat::Pango => Renderer::Pango(Pango::new()),
+                OutputFormat::Strip => Renderer::Strip,
+            },
+        }
+    }
+
+    /// Stream bytes from `reader` to `writer` through a filter with this configuration.
+    ///
+    /// On success returns the number of bytes written to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any call to [`read`] or [`write`] returns an error.
+    ///
+    /// [`read`]: std::io::Read::read
+    /// [`write`]: Write::write
+    #[cfg(feature = "std")]
+    pub fn run<R: BufRead, W: Write>(&self, reader: R, writer: &mut W) -> Result<u64> {
+        io::copy(&mut BufFilter::with_filter(reader, self.build()), writer)
+    }
+}
@@ -0,0 +1,312 @@
+use alloc::borrow::Cow;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::env;
+
+/// ANSI color parameters for IRC colors 0 through 98.
+///
+/// Colors 16 and up use the 256-color approximations of the mIRC extended colors.
+#[rustfmt::skip]
+const ANSI_COLORS: [&str; 99] = [
+    "7", "8;5;235", "4", "2", "1", "8;5;52", "5", "8;5;209",
+    "3", "8;5;47", "6", "6", "8;5;56", "8;5;200", "8;5;241", "7",
+    "8;5;52", "8;5;94", "8;5;100", "8;5;58", "8;5;22", "8;5;29", "8;5;23", "8;5;24", "8;5;17", "8;5;54", "8;5;53", "8;5;89",
+    "8;5;88", "8;5;130", "8;5;142", "8;5;64", "8;5;28", "8;5;35", "8;5;30", "8;5;25", "8;5;18", "8;5;91", "8;5;90", "8;5;125",
+    "8;5;124", "8;5;166", "8;5;184", "8;5;106", "8;5;34", "8;5;49", "8;5;37", "8;5;33", "8;5;19", "8;5;129", "8;5;127", "8;5;161",
+    "8;5;196", "8;5;208", "8;5;226", "8;5;154", "8;5;46", "8;5;86", "8;5;51", "8;5;75", "8;5;21", "8;5;171", "8;5;201", "8;5;198",
+    "8;5;203", "8;5;215", "8;5;227", "8;5;191", "8;5;83", "8;5;122", "8;5;87", "8;5;111", "8;5;63", "8;5;177", "8;5;207", "8;5;205",
+    "8;5;217", "8;5;223", "8;5;229", "8;5;193", "8;5;157", "8;5;158", "8;5;159", "8;5;153", "8;5;147", "8;5;183", "8;5;219", "8;5;212",
+    "8;5;16", "8;5;233", "8;5;235", "8;5;237", "8;5;239", "8;5;241", "8;5;244", "8;5;247", "8;5;250", "8;5;254", "8;5;231",
+];
+
+/// The kind of ANSI color sequences to emit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// The 8 standard and 8 bright colors (`\x1b[31m` and `\x1b[91m`).
+    ///
+    /// Each IRC color is mapped to the closest xterm color.
+    Ansi16,
+    /// The colors in the [`Palette`] which by default uses the 256-color palette
+    /// (`\x1b[38;5;196m`).
+    #[default]
+    Ansi256,
+    /// 24-bit colors matching the mIRC colors exactly (`\x1b[38;2;255;0;0m`).
+    TrueColor,
+    /// Whichever of the other modes the terminal supports according to [`ColorMode::detect`].
+    ///
+    /// Detection happens once when a filter is created.
+    Auto,
+}
+
+impl ColorMode {
+    /// Guess the color support of the terminal from the environment.
+    ///
+    /// `COLORTERM` set to `truecolor` or `24bit` selects [`ColorMode::TrueColor`], otherwise
+    /// `TERM` containing `256color` selects [`ColorMode::Ansi256`], and anything else falls back
+    /// to [`ColorMode::Ansi16`].
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn detect() -> Self {
+        Self::detect_from(
+            &env::var("COLORTERM").unwrap_or_default(),
+            &env::var("TERM").unwrap_or_default(),
+        )
+    }
+
+    /// Like [`ColorMode::detect`] with `colorterm` and `term` as the values of the variables.
+    #[cfg(feature = "std")]
+    pub(crate) fn detect_from(colorterm: &str, term: &str) -> Self {
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    /// Replace [`ColorMode::Auto`] with the detected mode.
+    ///
+    /// Without `std` there is no environment to detect from so the default is used.
+    pub(crate) fn resolve(self) -> Self {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Auto => Self::detect(),
+            #[cfg(not(feature = "std"))]
+            Self::Auto => Self::default(),
+            _ => self,
+        }
+    }
+}
+
+/// Mapping from IRC colors to ANSI color parameters.
+///
+/// Each entry is the part of the SGR parameter after the leading `3` or `4`, so `"1"` produces
+/// `\x1b[31m` as a foreground and `"8;5;196"` produces `\x1b[38;5;196m`. IRC color 99 always maps
+/// to the terminal's default color.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::Filter;
+/// # use ircat::IrcatBuilder;
+/// # use ircat::Palette;
+/// #
+/// let mut palette = Palette::default();
+/// palette.set(4, "8;5;196");
+/// let mut filter = IrcatBuilder::default().palette(palette).build();
+/// let mut output = Vec::new();
+/// filter.filter(b"\x034red\n", &mut output);
+/// filter.finish(&mut output);
+/// assert_eq!(output, b"\x1b[38;5;196mred\x1b[39m\n");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    colors: [Cow<'static, str>; 99],
+}
+
+impl Palette {
+    /// Get the ANSI color parameter for IRC color `index`.
+    #[must_use]
+    pub fn get(&self, index: u8) -> Option<&str> {
+        match self.colors.get(usize::from(index)) {
+            Some(color) => Some(color),
+            None => irc_to_ansi_color(index),
+        }
+    }
+
+    /// Set the ANSI color parameter for IRC color `index`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `index` is greater than 98.
+    pub fn set(&mut self, index: u8, color: impl Into<Cow<'static, str>>) {
+        self.colors[usize::from(index)] = color.into();
+    }
+
+    /// Create a palette telling the standard colors apart with red-green color blindness.
+    ///
+    /// The colors most often confused are moved towards the Okabe–Ito color-blind safe palette,
+    /// trading some faithfulness to the mIRC colors for contrast in hue and brightness:
+    ///
+    /// | IRC color        | Default        | Color-blind safe        |
+    /// |------------------|----------------|-------------------------|
+    /// | 2 (navy)         | `4` (blue)     | `8;5;25` (blue)         |
+    /// | 3 (green)        | `2` (green)    | `8;5;36` (bluish green) |
+    /// | 4 (red)          | `1` (red)      | `8;5;166` (vermillion)  |
+    /// | 5 (brown)        | `8;5;52`       | `8;5;94` (dark orange)  |
+    /// | 6 (purple)       | `5` (magenta)  | `8;5;175` (pink)        |
+    /// | 7 (orange)       | `8;5;209`      | `8;5;214` (orange)      |
+    /// | 8 (yellow)       | `3` (yellow)   | `8;5;221` (yellow)      |
+    /// | 9 (light green)  | `8;5;47`       | `8;5;79` (light teal)   |
+    /// | 12 (light blue)  | `8;5;56`       | `8;5;74` (sky blue)     |
+    ///
+    /// Every other color is the same as in [`Palette::default`].
+    #[must_use]
+    pub fn colorblind_safe() -> Self {
+        let mut palette = Self::default();
+        for (index, color) in [
+            (2, "8;5;25"),
+            (3, "8;5;36"),
+            (4, "8;5;166"),
+            (5, "8;5;94"),
+            (6, "8;5;175"),
+            (7, "8;5;214"),
+            (8, "8;5;221"),
+            (9, "8;5;79"),
+            (12, "8;5;74"),
+        ] {
+            palette.set(index, color);
+        }
+        palette
+    }
+
+    /// Choose whether IRC color 1 (black) is the terminal's black rather than the default dark
+    /// gray.
+    ///
+    /// The dark gray keeps black text readable on dark terminals while true black renders color
+    /// art faithfully.
+    pub fn set_true_black(&mut self, true_black: bool) {
+        self.set(1, if true_black { "0" } else { ANSI_COLORS[1] });
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            colors: ANSI_COLORS.map(Cow::Borrowed),
+        }
+    }
+}
+
+/// Get the default ANSI color parameter for IRC color `index`.
+///
+/// This is the mapping [`Palette::default`] starts from, in the same format as [`Palette::get`].
+/// Returns `None` for indices above 99.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::irc_to_ansi_color;
+/// #
+/// assert_eq!(irc_to_ansi_color(4), Some("1"));
+/// assert_eq!(irc_to_ansi_color(52), Some("8;5;196"));
+/// assert_eq!(irc_to_ansi_color(99), Some("9"));
+/// assert_eq!(irc_to_ansi_color(100), None);
+/// ```
+#[must_use]
+pub fn irc_to_ansi_color(index: u8) -> Option<&'static str> {
+    match index {
+        99 => Some("9"),
+        _ => ANSI_COLORS.get(usize::from(index)).copied(),
+    }
+}
+
+/// An IRC color displayed as the ANSI sequence switching the foreground to it.
+///
+/// This uses the same mapping as [`Palette::default`] so colors written with it match the output
+/// of [`ircat`](crate::ircat). Indices above 99 display as the default color.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::IrcColor;
+/// #
+/// assert_eq!(
+///     format!("{}red{} {}pink{}", IrcColor(4), IrcColor(99), IrcColor(13), IrcColor(99)),
+///     "\x1b[31mred\x1b[39m \x1b[38;5;200mpink\x1b[39m",
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IrcColor(pub u8);
+
+impl fmt::Display for IrcColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_sgr(f, true, irc_to_ansi_color(self.0).unwrap_or("9"))
+    }
+}
+
+/// Write the sequence switching the foreground or background to the palette entry `color`.
+pub(crate) fn write_sgr(f: &mut impl fmt::Write, foreground: bool, color: &str) -> fmt::Result {
+    write!(f, "\x1b[{}{color}m", if foreground { 3 } else { 4 })
+}
+
+/// RGB values of IRC colors 0 through 98 as used by mIRC.
+#[rustfmt::skip]
+#[allow(clippy::unreadable_literal)]
+const RGB_COLORS: [u32; 99] = [
+    0xffffff, 0x000000, 0x00007f, 0x009300, 0xff0000, 0x7f0000, 0x9c009c, 0xfc7f00,
+    0xffff00, 0x00fc00, 0x009393, 0x00ffff, 0x0000fc, 0xff00ff, 0x7f7f7f, 0xd2d2d2,
+    0x470000, 0x472100, 0x474700, 0x324700, 0x004700, 0x00472c, 0x004747, 0x002747, 0x000047, 0x2e0047, 0x470047, 0x47002a,
+    0x740000, 0x743a00, 0x747400, 0x517400, 0x007400, 0x007449, 0x007474, 0x004074, 0x000074, 0x4b0074, 0x740074, 0x740045,
+    0xb50000, 0xb56300, 0xb5b500, 0x7db500, 0x00b500, 0x00b571, 0x00b5b5, 0x0063b5, 0x0000b5, 0x7500b5, 0xb500b5, 0xb5006b,
+    0xff0000, 0xff8c00, 0xffff00, 0xb2ff00, 0x00ff00, 0x00ffa0, 0x00ffff, 0x008cff, 0x0000ff, 0xa500ff, 0xff00ff, 0xff0098,
+    0xff5959, 0xffb459, 0xffff71, 0xcfff60, 0x6fff6f, 0x65ffc9, 0x6dffff, 0x59b4ff, 0x5959ff, 0xc459ff, 0xff66ff, 0xff59bc,
+    0xff9c9c, 0xffd39c, 0xffff9c, 0xe2ff9c, 0x9cff9c, 0x9cffdb, 0x9cffff, 0x9cd3ff, 0x9c9cff, 0xdc9cff, 0xff9cff, 0xff94d3,
+    0x000000, 0x131313, 0x282828, 0x363636, 0x4d4d4d, 0x656565, 0x818181, 0x9f9f9f, 0xbcbcbc, 0xe2e2e2, 0xffffff,
+];
+
+pub(crate) fn lookup_rgb(color: u8) -> Option<u32> {
+    RGB_COLORS.get(usize::from(color)).copied()
+}
+
+/// RGB values of xterm's 16 standard colors.
+#[allow(clippy::unreadable_literal)]
+#[rustfmt::skip]
+const XTERM_COLORS: [u32; 16] = [
+    0x000000, 0xcd0000, 0x00cd00, 0xcdcd00, 0x0000ee, 0xcd00cd, 0x00cdcd, 0xe5e5e5,
+    0x7f7f7f, 0xff0000, 0x00ff00, 0xffff00, 0x5c5cff, 0xff00ff, 0x00ffff, 0xffffff,
+];
+
+/// The squared distance between two RGB colors.
+fn distance(a: u32, b: u32) -> i32 {
+    [16, 8, 0]
+        .into_iter()
+        .map(|shift| {
+            let a = i32::try_from((a >> shift) & 0xff).unwrap_or_default();
+            let b = i32::try_from((b >> shift) & 0xff).unwrap_or_default();
+            (a - b).pow(2)
+        })
+        .sum()
+}
+
+/// Find the xterm standard color closest to `rgb`.
+pub(crate) fn nearest_ansi16(rgb: u32) -> u8 {
+    (0..16)
+        .min_by_key(|index| distance(rgb, XTERM_COLORS[usize::from(*index)]))
+        .unwrap_or_default()
+}
+
+/// Find the color of xterm's 6×6×6 cube or grayscale ramp closest to `rgb`.
+///
+/// The standard colors are left out since terminals often change them.
+pub(crate) fn nearest_xterm256(rgb: u32) -> u8 {
+    // The cube levels are 0, 95, 135, 175, 215, and 255 so round to the nearest.
+    let level = |shift: u32| match (rgb >> shift) & 0xff {
+        0..=47 => 0,
+        48..=114 => 1,
+        value => u8::try_from((value - 35) / 40).unwrap_or_default(),
+    };
+    let cube = 16 + 36 * level(16) + 6 * level(8) + level(0);
+    // The ramp runs from 8 to 238 in steps of 10.
+    let average = ((rgb >> 16) + (rgb >> 8 & 0xff) + (rgb & 0xff)) / 3;
+    let gray = 232 + u8::try_from(average.saturating_sub(3) / 10).map_or(23, |step| step.min(23));
+    if distance(rgb, xterm_rgb(gray)) < distance(rgb, xterm_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// Find the RGB value of xterm 256 color `index`.
+pub(crate) fn xterm_rgb(index: u8) -> u32 {
+    match index {
+        0..=15 => XTERM_COLORS[usize::from(index)],
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + 40 * u32::from(n) };
+            let index = index - 16;
+            level(index / 36) << 16 | level(index / 6 % 6) << 8 | level(index % 6)
+        }
+        232..=255 => 0x01_01_01 * (8 + 10 * u32::from(index - 232)),
+    }
+}
@@ -0,0 +1,129 @@
+use alloc::vec::Vec;
+
+use crate::parser::Event;
+use crate::width::width;
+use crate::width::Whole;
+
+/// Counts the display columns of the output line by line.
+///
+/// This watches the events reaching the renderer so it counts the text as it is shown, with
+/// timestamps and wrapping, but not formatting, which takes no space.
+#[derive(Debug, Default)]
+pub(crate) struct Columns {
+    /// The column of the cursor on the current line.
+    column: usize,
+    /// The widest the current line was before a `\r` went back to its start.
+    widest: usize,
+    /// The widths of the lines ended and not taken yet.
+    lines: Vec<usize>,
+    /// A character split across texts, kept only to count its width.
+    whole: Whole,
+}
+
+impl Columns {
+    pub(crate) fn count(&mut self, event: Event<'_>) {
+        match event {
+            Event::Text(text) => self.whole.process(text, |text| self.column += width(text)),
+            Event::LineEnd(c) => {
+                self.whole.finish(|text| self.column += width(text));
+                self.widest = self.widest.max(self.column);
+                self.column = 0;
+                if c == b'\n' {
+                    self.lines.push(self.widest);
+                    self.widest = 0;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Take the widths of the lines ended so far.
+    pub(crate) fn lines(&mut self) -> impl Iterator<Item = usize> + '_ {
+        self.lines.drain(..)
+    }
+
+    /// End the line if anything is on it.
+    pub(crate) fn finish(&mut self) {
+        self.whole.finish(|text| self.column += width(text));
+        let widest = self.widest.max(self.column);
+        if widest != 0 {
+            self.lines.push(widest);
+        }
+        self.reset();
+    }
+
+    /// Start a new line without ending the current one.
+    pub(crate) fn reset(&mut self) {
+        self.column = 0;
+        self.widest = 0;
+        self.whole = Whole::default();
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use crate::Filter;
+    use crate::IrcatBuilder;
+    use crate::IrcatFilter;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $lines: expr, $column: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let input: &[u8] = $input.as_ref();
+                    let mut filter = IrcatBuilder::default().track_columns(true).build();
+                    filter.filter(input, &mut Vec::new());
+                    assert_eq!(filter.column(), Some($column));
+                    filter.finish(&mut Vec::new());
+                    assert_eq!(filter.line_widths().collect::<Vec<_>>(), $lines);
+
+                    let mut filter = IrcatBuilder::default().track_columns(true).build();
+                    for c in input {
+                        filter.filter(&[*c], &mut Vec::new());
+                    }
+                    filter.finish(&mut Vec::new());
+                    assert_eq!(filter.line_widths().collect::<Vec<_>>(), $lines, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (empty, b"", [0; 0], 0),
+        (plain, b"abc\nde\n", [3, 2], 0),
+        (colors, b"\x034,2abc\x03\n\x02d\x02e\x1f", [3, 2], 2),
+        (hex, b"\x04ff0000red\x04\nx", [3, 1], 1),
+        (unterminated, b"abc\nde", [3, 2], 2),
+        (empty_lines, b"\n\x02\n", [0, 0], 0),
+        (crlf, b"ab\r\ncd\r\n", [2, 2], 0),
+        (carriage_return, b"abcd\rxy\n", [4], 0),
+        (wide, "日本\ne\u{301}".as_bytes(), [4, 1], 1),
+    );
+
+    #[test]
+    fn stages() {
+        let mut filter = IrcatBuilder::default()
+            .track_columns(true)
+            .expand_tabs(4)
+            .wrap(6)
+            .timestamp(|output| output.extend_from_slice(b"> "))
+            .build();
+        filter.translate(b"a\tbcdef\n", &mut Vec::new());
+        assert_eq!(filter.line_widths().collect::<Vec<_>>(), [6, 3]);
+        assert_eq!(filter.line_widths().count(), 0);
+    }
+
+    #[test]
+    fn off() {
+        let mut filter = IrcatFilter::init();
+        filter.translate(b"abc\n", &mut Vec::new());
+        assert_eq!(filter.column(), None);
+        assert_eq!(filter.line_widths().count(), 0);
+    }
+}
@@ -0,0 +1,67 @@
+/// Make a console show the ANSI sequences written to stdout instead of printing them raw.
+///
+/// On Windows this turns on virtual terminal processing for the console stdout writes to, which
+/// consoles before Windows 10 lack. Other platforms' terminals always handle ANSI sequences so
+/// there this does nothing.
+///
+/// Returns whether ANSI sequences written to stdout are processed: `false` on Windows when stdout
+/// is not a console, such as when it is redirected to a file, or the console does not support
+/// them, and always `true` elsewhere.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::enable_ansi_console;
+/// #
+/// if enable_ansi_console() {
+///     println!("\x1b[31mred\x1b[39m");
+/// }
+/// ```
+#[must_use = "ANSI sequences may not be processed"]
+pub fn enable_ansi_console() -> bool {
+    #[cfg(windows)]
+    {
+        windows::enable_virtual_terminal_processing()
+    }
+    #[cfg(not(windows))]
+    {
+        true
+    }
+}
+
+#[cfg(windows)]
+#[allow(unsafe_code)]
+mod windows {
+    use core::ffi::c_void;
+
+    type Handle = *mut c_void;
+
+    /// `(DWORD)-11`
+    const STD_OUTPUT_HANDLE: u32 = 0xffff_fff5;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(std_handle: u32) -> Handle;
+        fn GetConsoleMode(console_handle: Handle, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console_handle: Handle, mode: u32) -> i32;
+    }
+
+    pub(super) fn enable_virtual_terminal_processing() -> bool {
+        // SAFETY: GetStdHandle takes no pointers, and the handle is checked before it is passed
+        // on with a pointer to a live local.
+        unsafe {
+            let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+            // Both null and INVALID_HANDLE_VALUE mean there is no usable stdout.
+            if handle.is_null() || handle as isize == -1 {
+                return false;
+            }
+            let mut mode = 0;
+            if GetConsoleMode(handle, &mut mode) == 0 {
+                return false;
+            }
+            mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+        }
+    }
+}
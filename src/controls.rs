@@ -0,0 +1,71 @@
+use crate::parser::Event;
+
+/// Remove control bytes other than tabs from the text.
+///
+/// Line endings and formatting codes never reach here as text so only stray bytes such as
+/// `\x01` or `\x07` are removed.
+pub(crate) fn process(event: Event<'_>, handle: &mut impl FnMut(Event<'_>)) {
+    let Event::Text(text) = event else {
+        handle(event);
+        return;
+    };
+    for text in text.split(|c| c.is_ascii_control() && *c != b'\t') {
+        if !text.is_empty() {
+            handle(Event::Text(text));
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+    use std::io::Result;
+
+    use crate::InputFormat;
+    use crate::IrcatBuilder;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let builder = IrcatBuilder::default().strip_controls(true);
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", b"foo bar"),
+        (ctcp, b"\x01ACTION waves\x01\n", b"ACTION waves\n"),
+        (bell, b"ding\x07\x07 dong\x7f", b"ding dong"),
+        (
+            formatting,
+            b"\x02bold\x02\x07\t\x034red\r\n",
+            b"\x1b[1mbold\x1b[22m\t\x1b[31mred\x1b[39m\r\n"
+        ),
+        (escape, b"\x1b[2Jclear", b"[2Jclear"),
+    );
+
+    #[test]
+    fn weechat() -> Result<()> {
+        let mut result = Vec::new();
+        IrcatBuilder::default()
+            .input(InputFormat::WeeChat)
+            .strip_controls(true)
+            .run(
+                &mut BufReader::new(b"\x02\x19F04red\x07".as_ref()),
+                &mut result,
+            )?;
+        assert_eq!(result, b"\x1b[31mred\x1b[39m");
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use crate::parser::Event;
+
+/// The start of a line holding a CTCP ACTION.
+const ACTION: &[u8] = b"\x01ACTION ";
+
+/// Renders CTCP ACTION messages as `* text` and removes the `\x01` around other CTCP messages.
+///
+/// An action has to start its line, before any formatting code, since that is where clients put
+/// it. The content of every CTCP message, formatting included, is passed through.
+#[derive(Debug)]
+pub(crate) struct Ctcp {
+    /// How much of [`ACTION`] the line started with so far or `None` once past its start.
+    matched: Option<usize>,
+}
+
+impl Ctcp {
+    pub(crate) fn new() -> Self {
+        Self { matched: Some(0) }
+    }
+
+    pub(crate) fn process(&mut self, event: Event<'_>, handle: &mut impl FnMut(Event<'_>)) {
+        match event {
+            Event::Text(mut text) => {
+                if let Some(matched) = self.matched {
+                    let len = ACTION[matched..]
+                        .iter()
+                        .zip(text)
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    if matched + len == ACTION.len() {
+                        self.matched = None;
+                        handle(Event::Text(b"* "));
+                        text = &text[len..];
+                    } else if len == text.len() {
+                        // The rest of the action may follow so wait for more.
+                        self.matched = Some(matched + len);
+                        return;
+                    } else {
+                        self.finish_start(handle);
+                    }
+                }
+                for text in text.split(|c| *c == b'\x01') {
+                    if !text.is_empty() {
+                        handle(Event::Text(text));
+                    }
+                }
+            }
+            Event::LineEnd(_) => {
+                self.finish_start(handle);
+                self.matched = Some(0);
+                handle(event);
+            }
+            _ => {
+                self.finish_start(handle);
+                handle(event);
+            }
+        }
+    }
+
+    /// Emit any text held back and start a new line.
+    pub(crate) fn finish(&mut self, handle: &mut impl FnMut(Event<'_>)) {
+        self.finish_start(handle);
+        self.matched = Some(0);
+    }
+
+    /// Emit the start of the line that turned out not to be an action.
+    fn finish_start(&mut self, handle: &mut impl FnMut(Event<'_>)) {
+        if let Some(matched) = self.matched.take() {
+            // Leave out the `\x01` like for any other CTCP message.
+            if matched > 1 {
+                handle(Event::Text(&ACTION[1..matched]));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+
+    use crate::IrcatBuilder;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let builder = IrcatBuilder::default().ctcp(true);
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", b"foo bar"),
+        (action, b"\x01ACTION waves\x01\n", b"* waves\n"),
+        (
+            action_color,
+            b"\x01ACTION \x034waves \x02hello\x02\x03\x01",
+            b"* \x1b[31mwaves \x1b[1mhello\x1b[22m\x1b[39m"
+        ),
+        (unterminated, b"\x01ACTION waves", b"* waves"),
+        (
+            lines,
+            b"hi\n\x01ACTION waves\x01\r\n\x01ACTION nods\x01",
+            b"hi\n* waves\r\n* nods"
+        ),
+        (other_ctcp, b"\x01VERSION\x01", b"VERSION"),
+        (partial, b"\x01ACT", b"ACT"),
+        (partial_line, b"\x01ACTIONS\x01\nx", b"ACTIONS\nx"),
+        (not_line_start, b"a \x01ACTION waves\x01", b"a ACTION waves"),
+        (
+            after_code,
+            b"\x02\x01ACTION waves\x01",
+            b"\x1b[1mACTION waves\x1b[22m"
+        ),
+        (plain_action, b"ACTION waves", b"ACTION waves"),
+    );
+}
@@ -1,31 +1,131 @@
-use std::cmp;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::cmp;
+#[cfg(feature = "std")]
 use std::io::BufRead;
+#[cfg(feature = "std")]
+use std::io::ErrorKind;
+#[cfg(feature = "std")]
 use std::io::Read;
+#[cfg(feature = "std")]
 use std::io::Result;
+#[cfg(feature = "std")]
+use std::io::Write;
 
-pub(crate) trait Filter: Sized {
+/// A streaming byte translator.
+///
+/// Input is handed to [`filter`] in arbitrarily sized chunks so any state needed to handle a
+/// sequence split across chunks must be kept in `self`.
+///
+/// [`filter`]: Filter::filter
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::Filter;
+/// #
+/// struct Upper;
+///
+/// impl Filter for Upper {
+///     fn init() -> Self {
+///         Self
+///     }
+///
+///     fn filter(&mut self, input: &[u8], output: &mut Vec<u8>) {
+///         output.extend(input.iter().map(u8::to_ascii_uppercase));
+///     }
+///
+///     fn finish(&mut self, _output: &mut Vec<u8>) {}
+/// }
+///
+/// let mut upper = Upper::init();
+/// let mut output = Vec::new();
+/// upper.filter(b"shout", &mut output);
+/// upper.finish(&mut output);
+/// assert_eq!(output, b"SHOUT");
+/// ```
+pub trait Filter: Sized {
+    /// Create a filter in its initial state.
     fn init() -> Self;
+
+    /// Translate the next chunk of `input`, appending the result to `output`.
+    ///
+    /// Implementations must only append to `output`.
     fn filter(&mut self, input: &[u8], output: &mut Vec<u8>);
+
+    /// Append anything still pending to `output` once the input is exhausted.
+    ///
+    /// This is called after the last call to [`filter`]. Afterwards the filter must be ready to
+    /// translate new input as if freshly initialized.
+    ///
+    /// [`filter`]: Filter::filter
+    fn finish(&mut self, output: &mut Vec<u8>);
 }
 
-pub(crate) struct BufFilter<F: Filter, R: BufRead> {
+/// Adapts a [`BufRead`] by passing everything read from it through a [`Filter`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BufFilter<F: Filter, R: BufRead> {
     inner: R,
     buffer: Vec<u8>,
     pos: usize,
     filter: F,
+    finished: bool,
+    /// The most input handed to the filter at once.
+    chunk_limit: usize,
 }
 
+#[cfg(feature = "std")]
 impl<F: Filter, R: BufRead> BufFilter<F, R> {
-    pub(crate) fn new(inner: R) -> Self {
+    /// Create a new `BufFilter` reading from `inner`.
+    pub fn new(inner: R) -> Self {
+        Self::with_filter(inner, F::init())
+    }
+
+    /// Create a new `BufFilter` reading from `inner` through an already constructed `filter`.
+    pub fn with_filter(inner: R, filter: F) -> Self {
         Self {
             inner,
             buffer: Vec::new(),
             pos: 0,
-            filter: F::init(),
+            filter,
+            finished: false,
+            chunk_limit: usize::MAX,
         }
     }
+
+    /// Get a reference to the filter.
+    pub fn get_filter(&self) -> &F {
+        &self.filter
+    }
+
+    /// Get a mutable reference to the filter.
+    ///
+    /// Output the filter already produced is still returned by later reads.
+    pub fn get_filter_mut(&mut self) -> &mut F {
+        &mut self.filter
+    }
+
+    /// Hand at most `limit` bytes of input to the filter at a time.
+    ///
+    /// By default everything the inner reader returns from [`fill_buf`] is translated at once so
+    /// a large read can produce an equally large translation. Limiting the chunk size bounds the
+    /// buffered output at the cost of more, smaller fill cycles. A `limit` of zero is treated as
+    /// one.
+    ///
+    /// This keeps memory bounded when tailing a long-running stream no matter how long its lines
+    /// get since output is handed on as each chunk is translated rather than once a line ends.
+    /// Codes split across chunks are kept by the filter until the rest of them arrives.
+    ///
+    /// [`fill_buf`]: BufRead::fill_buf
+    #[must_use]
+    pub fn with_chunk_limit(mut self, limit: usize) -> Self {
+        self.chunk_limit = cmp::max(limit, 1);
+        self
+    }
 }
 
+#[cfg(feature = "std")]
 impl<F: Filter, R: BufRead> Read for BufFilter<F, R> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
         let mut nread = 0;
@@ -48,15 +148,29 @@ impl<F: Filter, R: BufRead> Read for BufFilter<F, R> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<F: Filter, R: BufRead> BufRead for BufFilter<F, R> {
     fn fill_buf(&mut self) -> Result<&[u8]> {
         if self.pos >= self.buffer.len() {
             self.buffer.clear();
-            let buffer = self.inner.fill_buf()?;
-            self.filter.filter(buffer, &mut self.buffer);
-            let buffer_len = buffer.len();
-            self.inner.consume(buffer_len);
             self.pos = 0;
+            // An empty buffer signals EOF so keep reading until the filter produces something.
+            while self.buffer.is_empty() && !self.finished {
+                let buffer = match self.inner.fill_buf() {
+                    Ok(buffer) => buffer,
+                    // A signal arrived before anything was read so try again like `io::copy`.
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                };
+                if buffer.is_empty() {
+                    self.filter.finish(&mut self.buffer);
+                    self.finished = true;
+                } else {
+                    let buffer_len = cmp::min(buffer.len(), self.chunk_limit);
+                    self.filter.filter(&buffer[..buffer_len], &mut self.buffer);
+                    self.inner.consume(buffer_len);
+                }
+            }
         }
 
         Ok(&self.buffer[self.pos..])
@@ -66,3 +180,84 @@ impl<F: Filter, R: BufRead> BufRead for BufFilter<F, R> {
         self.pos = cmp::min(self.pos + amt, self.buffer.len());
     }
 }
+
+/// Adapts a [`Write`] by passing everything written to it through a [`Filter`].
+///
+/// Pending output such as a color left open is only written on [`flush`], after which the filter
+/// starts over. Dropping a `FilterWriter` without flushing it loses that output.
+///
+/// [`flush`]: Write::flush
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::Write;
+/// #
+/// # use ircat::FilterWriter;
+/// # use ircat::IrcatFilter;
+/// #
+/// let mut writer = FilterWriter::<IrcatFilter, _>::new(Vec::new());
+/// let name = "world";
+/// write!(writer, "hello \x034{name}")?;
+/// writer.flush()?;
+/// assert_eq!(writer.into_inner(), b"hello \x1b[31mworld\x1b[39m");
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FilterWriter<F: Filter, W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+    filter: F,
+}
+
+#[cfg(feature = "std")]
+impl<F: Filter, W: Write> FilterWriter<F, W> {
+    /// Create a new `FilterWriter` writing to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self::with_filter(inner, F::init())
+    }
+
+    /// Create a new `FilterWriter` writing to `inner` through an already constructed `filter`.
+    pub fn with_filter(inner: W, filter: F) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+            filter,
+        }
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the filter.
+    pub fn get_filter_mut(&mut self) -> &mut F {
+        &mut self.filter
+    }
+
+    /// Unwrap this `FilterWriter` returning the underlying writer.
+    ///
+    /// Nothing is flushed so any pending output is lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F: Filter, W: Write> Write for FilterWriter<F, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.buffer.clear();
+        self.filter.filter(buf, &mut self.buffer);
+        self.inner.write_all(&self.buffer)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.buffer.clear();
+        self.filter.finish(&mut self.buffer);
+        self.inner.write_all(&self.buffer)?;
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,175 @@
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::parser::Event;
+
+/// Highlights nicknames in the text with ANSI sequences.
+///
+/// This sits between a decoder and a renderer. Each nick is passed through as text surrounded by
+/// [`Event::Ansi`] sequences turning the highlight on and off. Nicks only match whole words
+/// ignoring ASCII case, where formatting codes end a word just like any byte that can not be part
+/// of a nick.
+#[derive(Clone, Debug)]
+pub(crate) struct Highlighter {
+    nicks: Vec<Vec<u8>>,
+    /// The sequence turning the highlight on.
+    on: Vec<u8>,
+    /// The sequence turning the highlight off.
+    off: Vec<u8>,
+    /// The length of the longest nick.
+    max_len: usize,
+    /// The word held back because it may be a nick.
+    word: Vec<u8>,
+    /// Whether the current word is too long to be a nick.
+    too_long: bool,
+}
+
+impl Highlighter {
+    /// Highlight `nicks` with the SGR parameters `on` and `off`.
+    pub(crate) fn new(nicks: Vec<Vec<u8>>, on: &str, off: &str) -> Self {
+        Self {
+            max_len: nicks.iter().map(Vec::len).max().unwrap_or_default(),
+            nicks,
+            on: format!("\x1b[{on}m").into_bytes(),
+            off: format!("\x1b[{off}m").into_bytes(),
+            word: Vec::new(),
+            too_long: false,
+        }
+    }
+
+    pub(crate) fn process(&mut self, event: Event<'_>, handle: &mut impl FnMut(Event<'_>)) {
+        match event {
+            Event::Text(text) => self.text(text, handle),
+            _ => {
+                self.finish(handle);
+                handle(event);
+            }
+        }
+    }
+
+    /// Emit any text held back.
+    pub(crate) fn finish(&mut self, handle: &mut impl FnMut(Event<'_>)) {
+        if !self.word.is_empty() {
+            if self
+                .nicks
+                .iter()
+                .any(|nick| nick.eq_ignore_ascii_case(&self.word))
+            {
+                handle(Event::Ansi(&self.on));
+                handle(Event::Text(&self.word));
+                handle(Event::Ansi(&self.off));
+            } else {
+                handle(Event::Text(&self.word));
+            }
+            self.word.clear();
+        }
+        self.too_long = false;
+    }
+
+    /// Forget any text held back without emitting it.
+    pub(crate) fn reset(&mut self) {
+        self.word.clear();
+        self.too_long = false;
+    }
+
+    fn text(&mut self, mut text: &[u8], handle: &mut impl FnMut(Event<'_>)) {
+        while !text.is_empty() {
+            let len = text
+                .iter()
+                .position(|c| !is_nick_byte(*c))
+                .unwrap_or(text.len());
+            let (word, rest) = text.split_at(len);
+            if self.too_long {
+                if !word.is_empty() {
+                    handle(Event::Text(word));
+                }
+            } else if self.word.len() + word.len() > self.max_len {
+                if !self.word.is_empty() {
+                    handle(Event::Text(&self.word));
+                    self.word.clear();
+                }
+                handle(Event::Text(word));
+                self.too_long = true;
+            } else {
+                self.word.extend_from_slice(word);
+            }
+            if rest.is_empty() {
+                // The word may go on in the next text.
+                return;
+            }
+
+            self.finish(handle);
+            let len = rest
+                .iter()
+                .position(|c| is_nick_byte(*c))
+                .unwrap_or(rest.len());
+            handle(Event::Text(&rest[..len]));
+            text = &rest[len..];
+        }
+    }
+}
+
+/// Whether `c` may be part of a nick.
+fn is_nick_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"-[]\\`^{}|_".contains(&c)
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+
+    use crate::IrcatBuilder;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let builder = IrcatBuilder::default()
+                        .ansi_passthrough(true)
+                        .highlight(["alice", "[bob]"], "7", "27");
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", b"foo bar"),
+        (nick, b"hi alice!", b"hi \x1b[7malice\x1b[27m!"),
+        (case, b"ALICE: hi", b"\x1b[7mALICE\x1b[27m: hi"),
+        (special, b"[bob], hi", b"\x1b[7m[bob]\x1b[27m, hi"),
+        (
+            next_to_color,
+            b"\x034alice\x03: \x02alice\x02",
+            b"\x1b[31m\x1b[7malice\x1b[27m\x1b[39m: \x1b[1m\x1b[7malice\x1b[27m\x1b[22m"
+        ),
+        (
+            lines,
+            b"alice\nalice\r\n",
+            b"\x1b[7malice\x1b[27m\n\x1b[7malice\x1b[27m\r\n"
+        ),
+        (
+            part_of_word,
+            b"malice alice_ alices",
+            b"malice alice_ alices"
+        ),
+        (
+            long_word,
+            b"alicealicealice alice",
+            b"alicealicealice \x1b[7malice\x1b[27m"
+        ),
+        (
+            escape,
+            b"\x1b[1malice\x1b[0m",
+            b"\x1b[1m\x1b[7malice\x1b[27m\x1b[0m"
+        ),
+    );
+}
@@ -0,0 +1,245 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::color::lookup_rgb;
+use crate::color::xterm_rgb;
+use crate::parser::Attribute;
+use crate::parser::Event;
+use crate::write::VecWriter;
+
+/// Renders parsed IRC formatting as HTML `<span>` elements.
+#[derive(Debug)]
+pub(crate) struct Html {
+    fg_color: Option<u32>,
+    bg_color: Option<u32>,
+    attributes: u8,
+    monospace: bool,
+    /// Whether a `<span>` is currently open in the output.
+    open: bool,
+}
+
+impl Html {
+    pub(crate) fn new() -> Self {
+        Self {
+            fg_color: None,
+            bg_color: None,
+            attributes: 0,
+            monospace: false,
+            open: false,
+        }
+    }
+
+    pub(crate) fn render(&mut self, event: Event<'_>, output: &mut Vec<u8>) {
+        match event {
+            Event::Text(text) => {
+                self.open(output);
+                escape(text, output);
+            }
+            Event::LineEnd(c) => {
+                self.reset(output);
+                output.push(c);
+            }
+            // Browsers ignore blinking text decoration so leave it out.
+            Event::Toggle(Attribute::Blink) => {}
+            Event::Toggle(attribute) => {
+                self.close(output);
+                self.attributes ^= attribute.mask();
+            }
+            Event::Monospace => {
+                self.close(output);
+                self.monospace = !self.monospace;
+            }
+            Event::Color(fg_color, bg_color) => {
+                self.close(output);
+                self.fg_color = lookup_rgb(fg_color);
+                if let Some(bg_color) = bg_color {
+                    self.bg_color = lookup_rgb(bg_color);
+                }
+            }
+            Event::HexColor(fg_color, bg_color) => {
+                self.close(output);
+                self.fg_color = Some(fg_color);
+                if let Some(bg_color) = bg_color {
+                    self.bg_color = Some(bg_color);
+                }
+            }
+            Event::Background(bg_color) => {
+                self.close(output);
+                self.bg_color = lookup_rgb(bg_color);
+            }
+            Event::HexBackground(bg_color) => {
+                self.close(output);
+                self.bg_color = Some(bg_color);
+            }
+            Event::XtermColor(fg_color, bg_color) => {
+                self.close(output);
+                self.fg_color = Some(xterm_rgb(fg_color));
+                if let Some(bg_color) = bg_color {
+                    self.bg_color = Some(xterm_rgb(bg_color));
+                }
+            }
+            Event::XtermBackground(bg_color) => {
+                self.close(output);
+                self.bg_color = Some(xterm_rgb(bg_color));
+            }
+            Event::ResetColor => {
+                self.close(output);
+                self.fg_color = None;
+                self.bg_color = None;
+            }
+            Event::Reset => self.reset(output),
+            // ANSI sequences have no meaning in HTML so drop them.
+            Event::Unknown | Event::Ansi(_) => {}
+        }
+    }
+
+    /// Whether any formatting is active.
+    pub(crate) fn is_active(&self) -> bool {
+        self.fg_color.is_some() || self.bg_color.is_some() || self.attributes != 0 || self.monospace
+    }
+
+    pub(crate) fn finish(&mut self, output: &mut Vec<u8>) {
+        self.reset(output);
+    }
+
+    fn reset(&mut self, output: &mut Vec<u8>) {
+        self.close(output);
+        self.fg_color = None;
+        self.bg_color = None;
+        self.attributes = 0;
+        self.monospace = false;
+    }
+
+    /// Open a `<span>` for the current formatting if there is any.
+    fn open(&mut self, output: &mut Vec<u8>) {
+        if self.open {
+            return;
+        }
+
+        let mut style = Vec::new();
+        if let Some(fg_color) = self.fg_color {
+            style.push(format!("color:#{fg_color:06x}"));
+        }
+        if let Some(bg_color) = self.bg_color {
+            style.push(format!("background-color:#{bg_color:06x}"));
+        }
+        if self.attributes & Attribute::Bold.mask() != 0 {
+            style.push("font-weight:bold".to_owned());
+        }
+        if self.attributes & Attribute::Italic.mask() != 0 {
+            style.push("font-style:italic".to_owned());
+        }
+        // Reverse has no CSS equivalent so it is ignored.
+        match (
+            self.attributes & Attribute::Underline.mask() != 0,
+            self.attributes & Attribute::Strikethrough.mask() != 0,
+        ) {
+            (false, false) => {}
+            (true, false) => style.push("text-decoration:underline".to_owned()),
+            (false, true) => style.push("text-decoration:line-through".to_owned()),
+            (true, true) => style.push("text-decoration:underline line-through".to_owned()),
+        }
+        if self.monospace {
+            style.push("font-family:monospace".to_owned());
+        }
+
+        if !style.is_empty() {
+            // Writing to a Vec can't fail.
+            let _ = write!(VecWriter(output), "<span style=\"{}\">", style.join(";"));
+            self.open = true;
+        }
+    }
+
+    fn close(&mut self, output: &mut Vec<u8>) {
+        if self.open {
+            output.extend_from_slice(b"</span>");
+            self.open = false;
+        }
+    }
+}
+
+fn escape(text: &[u8], output: &mut Vec<u8>) {
+    for c in text {
+        match c {
+            b'&' => output.extend_from_slice(b"&amp;"),
+            b'<' => output.extend_from_slice(b"&lt;"),
+            b'>' => output.extend_from_slice(b"&gt;"),
+            _ => output.push(*c),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+
+    use crate::ircat_html;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let mut result = Vec::new();
+                    ircat_html(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(String::from_utf8(result).unwrap(), $expected);
+
+                    let mut result = Vec::new();
+                    ircat_html(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(String::from_utf8(result).unwrap(), $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", "foo bar"),
+        (escape, b"a < b && c > d", "a &lt; b &amp;&amp; c &gt; d"),
+        (
+            color,
+            b"\x034<b>",
+            "<span style=\"color:#ff0000\">&lt;b&gt;</span>"
+        ),
+        (
+            background,
+            b"\x034,2text",
+            "<span style=\"color:#ff0000;background-color:#00007f\">text</span>"
+        ),
+        (
+            newline,
+            b"\x034red\nplain\n",
+            "<span style=\"color:#ff0000\">red</span>\nplain\n"
+        ),
+        (
+            reset_color,
+            b"\x034red\x03plain",
+            "<span style=\"color:#ff0000\">red</span>plain"
+        ),
+        (
+            reset_color_keeps_bold,
+            b"\x02\x034bold-red\x03still-bold",
+            "<span style=\"color:#ff0000;font-weight:bold\">bold-red</span>\
+             <span style=\"font-weight:bold\">still-bold</span>"
+        ),
+        (
+            change_color,
+            b"\x034\x033green",
+            "<span style=\"color:#009300\">green</span>"
+        ),
+        (
+            attributes,
+            b"\x02bold\x1dboth\x0fplain",
+            "<span style=\"font-weight:bold\">bold</span>\
+             <span style=\"font-weight:bold;font-style:italic\">both</span>plain"
+        ),
+        (default_color, b"\x0399text", "text"),
+        (
+            hex,
+            b"\x04ff8000,00007ftext",
+            "<span style=\"color:#ff8000;background-color:#00007f\">text</span>"
+        ),
+    );
+}
@@ -0,0 +1,287 @@
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::parser::Event;
+
+/// The schemes of the URLs that are linked.
+const SCHEMES: [&[u8]; 2] = [b"https://", b"http://"];
+
+/// The longest URL linked, longer ones are left as text so memory stays bounded.
+const MAX_URL_LEN: usize = 4096;
+
+/// Wraps `http` and `https` URLs in the text in OSC 8 hyperlinks.
+///
+/// This sits between a decoder and a renderer. Each URL is passed through as text surrounded by
+/// [`Event::Ansi`] sequences opening and closing the link. A URL ends at a formatting code or at
+/// the first byte that is not printable ASCII, and trailing punctuation is left out of it.
+#[derive(Debug)]
+pub(crate) struct Hyperlinks {
+    /// Text held back because it is, or may be the start of, a URL.
+    pending: Vec<u8>,
+    /// Whether `pending` starts with a complete scheme.
+    in_url: bool,
+    /// Whether the text is inside a URL too long to link.
+    too_long: bool,
+    /// The sequence opening the current link.
+    sequence: Vec<u8>,
+}
+
+impl Hyperlinks {
+    pub(crate) fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            in_url: false,
+            too_long: false,
+            sequence: Vec::new(),
+        }
+    }
+
+    pub(crate) fn process(&mut self, event: Event<'_>, handle: &mut impl FnMut(Event<'_>)) {
+        match event {
+            Event::Text(text) if self.pending.is_empty() => self.text(text, handle),
+            Event::Text(text) => {
+                let mut pending = mem::take(&mut self.pending);
+                pending.extend_from_slice(text);
+                self.text(&pending, handle);
+                // Keep the allocation for next time.
+                if self.pending.is_empty() {
+                    pending.clear();
+                    self.pending = pending;
+                }
+            }
+            _ => {
+                self.finish(handle);
+                handle(event);
+            }
+        }
+    }
+
+    /// Emit any text held back.
+    pub(crate) fn finish(&mut self, handle: &mut impl FnMut(Event<'_>)) {
+        let pending = mem::take(&mut self.pending);
+        self.too_long = false;
+        if self.in_url {
+            self.in_url = false;
+            self.link(&pending, handle);
+        } else if !pending.is_empty() {
+            handle(Event::Text(&pending));
+        }
+        self.pending = pending;
+        self.pending.clear();
+    }
+
+    fn text(&mut self, mut text: &[u8], handle: &mut impl FnMut(Event<'_>)) {
+        while !text.is_empty() {
+            if self.too_long {
+                let end = text
+                    .iter()
+                    .position(|c| !c.is_ascii_graphic())
+                    .unwrap_or(text.len());
+                if end != 0 {
+                    handle(Event::Text(&text[..end]));
+                }
+                self.too_long = end == text.len();
+                text = &text[end..];
+                continue;
+            }
+
+            if self.in_url {
+                let Some(end) = text.iter().position(|c| !c.is_ascii_graphic()) else {
+                    if self.pending.len() + text.len() > MAX_URL_LEN {
+                        handle(Event::Text(&self.pending));
+                        handle(Event::Text(text));
+                        self.pending.clear();
+                        self.in_url = false;
+                        self.too_long = true;
+                    } else {
+                        self.pending.extend_from_slice(text);
+                    }
+                    return;
+                };
+                self.in_url = false;
+                self.link(&text[..end], handle);
+                text = &text[end..];
+                continue;
+            }
+
+            match find_scheme(text) {
+                Some((start, true)) => {
+                    if start != 0 {
+                        handle(Event::Text(&text[..start]));
+                    }
+                    self.in_url = true;
+                    text = &text[start..];
+                }
+                Some((start, false)) => {
+                    // The rest may be the start of a scheme so wait for more.
+                    if start != 0 {
+                        handle(Event::Text(&text[..start]));
+                    }
+                    self.pending.extend_from_slice(&text[start..]);
+                    return;
+                }
+                None => {
+                    handle(Event::Text(text));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Emit `url` as a link and any trailing punctuation after it as text.
+    fn link(&mut self, url: &[u8], handle: &mut impl FnMut(Event<'_>)) {
+        let len = url.len()
+            - url
+                .iter()
+                .rev()
+                .take_while(|c| b".,:;!?'\")]}".contains(c))
+                .count();
+        let (url, rest) = url.split_at(len);
+        if SCHEMES.contains(&url) || url.len() > MAX_URL_LEN {
+            // Only a scheme is not worth linking.
+            handle(Event::Text(url));
+        } else {
+            self.sequence.clear();
+            self.sequence.extend_from_slice(b"\x1b]8;;");
+            self.sequence.extend_from_slice(url);
+            self.sequence.extend_from_slice(b"\x1b\\");
+            handle(Event::Ansi(&self.sequence));
+            handle(Event::Text(url));
+            handle(Event::Ansi(b"\x1b]8;;\x1b\\"));
+        }
+        if !rest.is_empty() {
+            handle(Event::Text(rest));
+        }
+    }
+}
+
+/// Find the first scheme in `text`.
+///
+/// Returns where it starts and whether it is complete, or incomplete because `text` ends first.
+fn find_scheme(text: &[u8]) -> Option<(usize, bool)> {
+    let mut i = 0;
+    while let Some(start) = text[i..].iter().position(|c| *c == b'h') {
+        let rest = &text[i + start..];
+        for scheme in SCHEMES {
+            if rest.starts_with(scheme) {
+                return Some((i + start, true));
+            }
+        }
+        if SCHEMES.iter().any(|scheme| scheme.starts_with(rest)) {
+            return Some((i + start, false));
+        }
+        i += start + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+    use std::io::Result;
+
+    use crate::IrcatBuilder;
+    use crate::OutputFormat;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let builder = IrcatBuilder::default().hyperlinks(true);
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", b"foo bar"),
+        (
+            url,
+            b"see https://example.com/a?b=c now",
+            b"see \x1b]8;;https://example.com/a?b=c\x1b\\https://example.com/a?b=c\x1b]8;;\x1b\\ now"
+        ),
+        (
+            http,
+            b"http://example.com",
+            b"\x1b]8;;http://example.com\x1b\\http://example.com\x1b]8;;\x1b\\"
+        ),
+        (
+            colored,
+            b"\x034https://example.com\x03 done",
+            b"\x1b[31m\x1b]8;;https://example.com\x1b\\https://example.com\x1b]8;;\x1b\\\x1b[39m done"
+        ),
+        (
+            colored_inside,
+            b"\x02see \x034https://example.com \x02bold",
+            b"\x1b[1msee \x1b[31m\x1b]8;;https://example.com\x1b\\https://example.com\x1b]8;;\x1b\\ \
+              \x1b[22mbold\x1b[39m"
+        ),
+        (
+            punctuation,
+            b"(https://example.com/page).",
+            b"(\x1b]8;;https://example.com/page\x1b\\https://example.com/page\x1b]8;;\x1b\\)."
+        ),
+        (
+            line_end,
+            b"https://a.example\nhttps://b.example\n",
+            b"\x1b]8;;https://a.example\x1b\\https://a.example\x1b]8;;\x1b\\\n\
+              \x1b]8;;https://b.example\x1b\\https://b.example\x1b]8;;\x1b\\\n"
+        ),
+        (scheme_only, b"https:// and http://", b"https:// and http://"),
+        (partial_scheme, b"an htt at the end htt", b"an htt at the end htt"),
+        (other_scheme, b"ftp://example.com", b"ftp://example.com"),
+    );
+
+    #[test]
+    fn too_long() -> Result<()> {
+        let mut url = b"https://example.com/".to_vec();
+        url.extend_from_slice(&[b'a'; 5000]);
+        let mut input = url.clone();
+        input.extend_from_slice(b" https://example.com");
+        for capacity in [1, 8192] {
+            let mut result = Vec::new();
+            IrcatBuilder::default().hyperlinks(true).run(
+                &mut BufReader::with_capacity(capacity, input.as_slice()),
+                &mut result,
+            )?;
+            let mut expected = url.clone();
+            expected.extend_from_slice(
+                b" \x1b]8;;https://example.com\x1b\\https://example.com\x1b]8;;\x1b\\",
+            );
+            assert_eq!(result, expected, "capacity {capacity}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn other_outputs() -> Result<()> {
+        for (output, expected) in [
+            (
+                OutputFormat::Html,
+                b"<span style=\"color:#ff0000\">https://example.com</span>".as_ref(),
+            ),
+            (OutputFormat::Irc, b"\x034https://example.com"),
+            (OutputFormat::Strip, b"https://example.com"),
+        ] {
+            let mut result = Vec::new();
+            IrcatBuilder::default()
+                .hyperlinks(true)
+                .output(output)
+                .run(
+                    &mut BufReader::new(b"\x034https://example.com".as_ref()),
+                    &mut result,
+                )?;
+            assert_eq!(result, expected, "{output:?}");
+        }
+        Ok(())
+    }
+}
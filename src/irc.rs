@@ -0,0 +1,345 @@
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::color::lookup_rgb;
+use crate::color::xterm_rgb;
+use crate::parser::Attribute;
+use crate::parser::Event;
+use crate::write::VecWriter;
+
+/// IRC codes toggling each attribute.
+const CODES: [(Attribute, u8); 5] = [
+    (Attribute::Bold, b'\x02'),
+    (Attribute::Italic, b'\x1d'),
+    (Attribute::Underline, b'\x1f'),
+    (Attribute::Strikethrough, b'\x1e'),
+    (Attribute::Reverse, b'\x16'),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    Irc(u8),
+    Rgb(u32),
+}
+
+impl Color {
+    /// The color for IRC color `index` where 99 is the default color.
+    fn irc(index: u8) -> Option<Self> {
+        lookup_rgb(index).map(|_| Self::Irc(index))
+    }
+
+    fn rgb(self) -> u32 {
+        match self {
+            Self::Irc(index) => lookup_rgb(index).unwrap_or_default(),
+            Self::Rgb(rgb) => rgb,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Format {
+    fg_color: Option<Color>,
+    bg_color: Option<Color>,
+    attributes: u8,
+    monospace: bool,
+}
+
+/// Renders parsed formatting as minimal IRC codes.
+///
+/// Codes are only written once text follows them so codes without effect are dropped, and each
+/// color uses as few digits as it can without running into the text after it.
+#[derive(Debug)]
+pub(crate) struct Irc {
+    /// The formatting set by the input so far.
+    format: Format,
+    /// The formatting of the output so far.
+    written: Format,
+}
+
+impl Irc {
+    pub(crate) fn new() -> Self {
+        Self {
+            format: Format::default(),
+            written: Format::default(),
+        }
+    }
+
+    pub(crate) fn render(&mut self, event: Event<'_>, output: &mut Vec<u8>) {
+        match event {
+            Event::Text(text) => {
+                self.write(text.first().copied(), output);
+                output.extend_from_slice(text);
+            }
+            Event::LineEnd(c) => {
+                // Formatting ends with the line so nothing needs resetting.
+                *self = Self::new();
+                output.push(c);
+            }
+            // IRC has no code for blink.
+            Event::Toggle(Attribute::Blink) => {}
+            Event::Toggle(attribute) => self.format.attributes ^= attribute.mask(),
+            Event::Monospace => self.format.monospace = !self.format.monospace,
+            Event::Color(fg_color, bg_color) => {
+                self.format.fg_color = Color::irc(fg_color);
+                if let Some(bg_color) = bg_color {
+                    self.format.bg_color = Color::irc(bg_color);
+                }
+            }
+            Event::HexColor(fg_color, bg_color) => {
+                self.format.fg_color = Some(Color::Rgb(fg_color));
+                if let Some(bg_color) = bg_color {
+                    self.format.bg_color = Some(Color::Rgb(bg_color));
+                }
+            }
+            Event::Background(bg_color) => self.format.bg_color = Color::irc(bg_color),
+            Event::HexBackground(bg_color) => self.format.bg_color = Some(Color::Rgb(bg_color)),
+            // IRC has no codes for xterm colors so give them as hex colors.
+            Event::XtermColor(fg_color, bg_color) => {
+                self.format.fg_color = Some(Color::Rgb(xterm_rgb(fg_color)));
+                if let Some(bg_color) = bg_color {
+                    self.format.bg_color = Some(Color::Rgb(xterm_rgb(bg_color)));
+                }
+            }
+            Event::XtermBackground(bg_color) => {
+                self.format.bg_color = Some(Color::Rgb(xterm_rgb(bg_color)));
+            }
+            Event::ResetColor => {
+                self.format.fg_color = None;
+                self.format.bg_color = None;
+            }
+            Event::Reset => self.format = Format::default(),
+            // Whatever was not understood is printed as text which follows.
+            Event::Unknown => {}
+            Event::Ansi(sequence) => {
+                self.write(sequence.first().copied(), output);
+                output.extend_from_slice(sequence);
+            }
+        }
+    }
+
+    /// Whether any formatting is active.
+    pub(crate) fn is_active(&self) -> bool {
+        self.format != Format::default()
+    }
+
+    pub(crate) fn finish(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Write the codes changing the formatting of the output to that of the input before `next`.
+    fn write(&mut self, next: Option<u8>, output: &mut Vec<u8>) {
+        if self.written == self.format {
+            return;
+        }
+        let Format {
+            fg_color,
+            bg_color,
+            attributes,
+            monospace,
+        } = self.format;
+        if self.format == Format::default()
+            && (self.written.attributes != 0 || self.written.monospace)
+        {
+            output.push(b'\x0f');
+            self.written = self.format;
+            return;
+        }
+
+        let toggles = attributes != self.written.attributes || monospace != self.written.monospace;
+        // Toggles are written after the colors so keep them apart from the text.
+        let next = if toggles { None } else { next };
+        if fg_color.is_none() && bg_color.is_none() {
+            if self.written.fg_color.is_some() || self.written.bg_color.is_some() {
+                if next.is_some_and(|c| c.is_ascii_digit()) {
+                    // A bare reset would take the digit as a color.
+                    output.extend_from_slice(b"\x0399,99");
+                } else {
+                    output.push(b'\x03');
+                }
+            }
+        } else if fg_color != self.written.fg_color || bg_color != self.written.bg_color {
+            write_color(
+                fg_color,
+                bg_color,
+                bg_color != self.written.bg_color,
+                next,
+                output,
+            );
+        }
+
+        for (attribute, code) in CODES {
+            if (attributes ^ self.written.attributes) & attribute.mask() != 0 {
+                output.push(code);
+            }
+        }
+        if monospace != self.written.monospace {
+            output.push(b'\x11');
+        }
+        self.written = self.format;
+    }
+}
+
+/// Write codes setting the foreground to `fg_color`, and the background to `bg_color` if
+/// `set_bg`, that do not run into `next`.
+fn write_color(
+    fg_color: Option<Color>,
+    bg_color: Option<Color>,
+    set_bg: bool,
+    next: Option<u8>,
+    output: &mut Vec<u8>,
+) {
+    let width = if next.is_some_and(|c| c.is_ascii_digit()) {
+        2
+    } else {
+        1
+    };
+    let index = |color| match color {
+        Some(Color::Irc(index)) => index,
+        _ => 99,
+    };
+    // A comma following a foreground would be taken as the start of a background.
+    let next_comma = next == Some(b',');
+    let keep_apart = |output: &mut Vec<u8>| {
+        if next_comma {
+            output.extend_from_slice(b"\x02\x02");
+        }
+    };
+
+    // Writing to a Vec can't fail.
+    match (fg_color, bg_color) {
+        (Some(Color::Rgb(fg_color)), _) => {
+            if set_bg {
+                // A hex code only gives a hex background so set any other beforehand.
+                match bg_color {
+                    Some(Color::Rgb(bg_color)) => {
+                        let _ = write!(VecWriter(output), "\x04{fg_color:06x},{bg_color:06x}");
+                        return;
+                    }
+                    Some(Color::Irc(bg_color)) => {
+                        let _ = write!(VecWriter(output), "\x0399,{bg_color}");
+                    }
+                    None => output.push(b'\x03'),
+                }
+            }
+            let _ = write!(VecWriter(output), "\x04{fg_color:06x}");
+            keep_apart(output);
+        }
+        (_, Some(Color::Rgb(bg_color))) => {
+            // Only a hex code gives a hex background so set the foreground afterwards.
+            if set_bg {
+                let placeholder = fg_color.map_or(0, Color::rgb);
+                let _ = write!(VecWriter(output), "\x04{placeholder:06x},{bg_color:06x}");
+            }
+            let _ = write!(VecWriter(output), "\x03{:0width$}", index(fg_color));
+            keep_apart(output);
+        }
+        _ if set_bg || next_comma => {
+            let _ = write!(
+                VecWriter(output),
+                "\x03{},{:0width$}",
+                index(fg_color),
+                index(bg_color)
+            );
+        }
+        _ => {
+            let _ = write!(VecWriter(output), "\x03{:0width$}", index(fg_color));
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+
+    use crate::IrcatBuilder;
+    use crate::OutputFormat;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let builder = IrcatBuilder::default().output(OutputFormat::Irc);
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", b"foo bar"),
+        (tidy, b"\x034red\x03 \x02bold\x02", b"\x034red\x03 \x02bold"),
+        (leading_zeros, b"\x0304,02text", b"\x034,2text"),
+        (leading_zero_white, b"\x0300white", b"\x030white"),
+        (digit_following, b"\x03044th", b"\x03044th"),
+        (bg_digit_following, b"\x034,024th", b"\x034,024th"),
+        (comma_following, b"\x034,x", b"\x034,99,x"),
+        (comma_following_bg, b"\x034,2a\x035,b", b"\x034,2a\x035,2,b"),
+        (redundant_reset, b"\x0f\x03plain\x0f", b"plain"),
+        (
+            reset_color_digit,
+            b"\x034a\x03\x02\x021",
+            b"\x034a\x0399,991"
+        ),
+        (reset_color_toggle, b"\x034a\x03\x021", b"\x034a\x03\x021"),
+        (overridden, b"\x034\x035\x02\x02text", b"\x035text"),
+        (trailing_codes, b"text\x034\x02", b"text"),
+        (
+            reset_all,
+            b"\x02\x1d\x034text\x0fplain",
+            b"\x034\x02\x1dtext\x0fplain"
+        ),
+        (
+            default_color,
+            b"\x034red\x0399default",
+            b"\x034red\x03default"
+        ),
+        (bg_only, b"\x034,2a\x0399,2b", b"\x034,2a\x0399b"),
+        (drop_bg, b"\x034,2a\x034,99b", b"\x034,2a\x034,99b"),
+        (same_color, b"\x034a\x034b", b"\x034ab"),
+        (newline, b"\x034a\n\x034b", b"\x034a\n\x034b"),
+        (newline_reset, b"\x034a\n\x03b", b"\x034a\nb"),
+        (hex, b"\x04FF0000,00ff00text", b"\x04ff0000,00ff00text"),
+        (hex_comma, b"\x04ff0000,x", b"\x04ff0000\x02\x02,x"),
+        (
+            hex_fg_irc_bg,
+            b"\x034,2\x04ff8000text",
+            b"\x0399,2\x04ff8000text"
+        ),
+        (
+            hex_fg_default_bg,
+            b"\x04ff8000,000000a\x03\x04ff8000b",
+            b"\x04ff8000,000000a\x03\x04ff8000b"
+        ),
+        (
+            irc_fg_hex_bg,
+            b"\x04ff8000,00007fa\x032b",
+            b"\x04ff8000,00007fa\x032b"
+        ),
+        (
+            irc_fg_new_hex_bg,
+            b"\x032\x04ff8000,00007f\x032b",
+            b"\x0400007f,00007f\x032b"
+        ),
+        (
+            default_fg_hex_bg,
+            b"\x04ff8000,00007fa\x0399b",
+            b"\x04ff8000,00007fa\x0399b"
+        ),
+        (
+            irc_fg_hex_bg_comma,
+            b"\x04ff8000,00007fa\x032,b",
+            b"\x04ff8000,00007fa\x032\x02\x02,b"
+        ),
+        (monospace, b"\x11code\x11", b"\x11code"),
+        (attributes, b"\x1d\x1f\x1e\x16text", b"\x1d\x1f\x1e\x16text"),
+        (unknown, b"\x04ff text", b"ff text"),
+    );
+}
@@ -14,20 +14,114 @@
     rustdoc::unescaped_backticks
 )]
 #![allow(clippy::match_same_arms, clippy::single_match_else)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::env;
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+#[cfg(feature = "std")]
 use std::io;
+#[cfg(feature = "std")]
 use std::io::BufRead;
+#[cfg(feature = "std")]
+use std::io::BufReader;
+#[cfg(feature = "std")]
+use std::io::ErrorKind;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
 use std::io::Result;
+#[cfg(feature = "std")]
 use std::io::Write;
+#[cfg(feature = "std")]
+use std::result;
 
-use crate::filter::BufFilter;
-use crate::filter::Filter;
+use crate::ansi::Ansi;
+pub use crate::builder::InputFormat;
+pub use crate::builder::IrcatBuilder;
+pub use crate::builder::OutputFormat;
+pub use crate::color::irc_to_ansi_color;
+pub use crate::color::ColorMode;
+pub use crate::color::IrcColor;
+pub use crate::color::Palette;
+use crate::columns::Columns;
+#[cfg(feature = "std")]
+pub use crate::console::enable_ansi_console;
+use crate::ctcp::Ctcp;
+#[cfg(feature = "std")]
+pub use crate::filter::BufFilter;
+pub use crate::filter::Filter;
+#[cfg(feature = "std")]
+pub use crate::filter::FilterWriter;
+use crate::highlight::Highlighter;
+use crate::html::Html;
+use crate::hyperlink::Hyperlinks;
+use crate::irc::Irc;
+use crate::pango::Pango;
+pub use crate::parser::Attribute;
+pub use crate::parser::Bell;
+use crate::parser::Event;
+use crate::parser::Parser;
+#[cfg(feature = "std")]
+pub use crate::record::replay;
+#[cfg(feature = "std")]
+pub use crate::record::Frame;
+#[cfg(feature = "std")]
+pub use crate::record::Recorder;
+pub use crate::stats::color_histogram;
+pub use crate::stats::Stats;
+use crate::tabs::Tabs;
+use crate::timestamp::Timestamps;
+pub use crate::tokenize::Token;
+pub use crate::tokenize::Tokenizer;
+pub use crate::validate::validate;
+pub use crate::validate::FormatError;
+#[cfg(feature = "std")]
+pub use crate::validate::IrcatError;
+#[cfg(feature = "std")]
+use crate::validate::Validator;
+use crate::weechat::WeeChatParser;
+use crate::wrap::Wrap;
 
+mod ansi;
+mod builder;
+mod color;
+mod columns;
+#[cfg(feature = "std")]
+mod console;
+mod controls;
+mod ctcp;
 mod filter;
+mod highlight;
+mod html;
+mod hyperlink;
+mod irc;
+mod pango;
+mod parser;
+#[cfg(feature = "std")]
+mod record;
+mod stats;
+mod strip;
+mod tabs;
+mod timestamp;
+mod tokenize;
+mod validate;
+mod weechat;
+mod width;
+mod wrap;
+mod write;
 
 /// Stream bytes from `reader` to `writer` while translating IRC color codes into ANSI ones.
 ///
-/// On success returns the number of bytes written to `writer`.
+/// IRC color 99 is rendered as the terminal's default color. On success returns the number of
+/// bytes written to `writer`.
 ///
 /// # Errors
 ///
@@ -36,10 +130,6 @@ mod filter;
 /// [`read`]: std::io::Read::read
 /// [`write`]: Write::write
 ///
-/// # Panics
-///
-/// This function will panic if an unknown IRC color is encountered.
-///
 /// # Examples
 ///
 /// ```
@@ -49,200 +139,2367 @@ mod filter;
 /// #
 /// let mut writer = Vec::new();
 /// ircat(&mut BufReader::new(b"Colors \x034red \x033green \x032blue\n".as_ref()), &mut writer)?;
-/// assert_eq!(writer, b"Colors \x1b[31mred \x1b[32mgreen \x1b[34mblue\x1b[39m\x1b[49m\n");
+/// assert_eq!(writer, b"Colors \x1b[31mred \x1b[32mgreen \x1b[34mblue\x1b[39m\n");
 /// # std::io::Result::Ok(())
 /// ```
+#[cfg(feature = "std")]
 pub fn ircat<R: BufRead, W: Write>(reader: R, writer: &mut W) -> Result<u64> {
-    io::copy(&mut BufFilter::<IRCatFilter, R>::new(reader), writer)
+    IrcatBuilder::default().run(reader, writer)
 }
 
-enum IRCatState {
-    Normal,
-    Start,
-    Foreground1(u8),
-    Foreground2,
-    Comma,
-    Background1(u8),
+/// Like [`ircat`] but read from any [`Read`], buffering it internally.
+///
+/// # Errors
+///
+/// This function will return an error if any call to [`read`] or [`write`] returns an error.
+///
+/// [`read`]: Read::read
+/// [`write`]: Write::write
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::ircat_read;
+/// #
+/// let mut writer = Vec::new();
+/// ircat_read(b"Colors \x034red\n".as_ref(), &mut writer)?;
+/// assert_eq!(writer, b"Colors \x1b[31mred\x1b[39m\n");
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+pub fn ircat_read<R: Read, W: Write>(reader: R, writer: &mut W) -> Result<u64> {
+    ircat(BufReader::new(reader), writer)
 }
 
-struct IRCatFilter {
-    state: IRCatState,
-    in_color: bool,
+/// Like [`ircat`] but map IRC colors to ANSI colors using `palette`.
+///
+/// # Errors
+///
+/// This function will return an error if any call to [`read`] or [`write`] returns an error.
+///
+/// [`read`]: std::io::Read::read
+/// [`write`]: Write::write
+#[cfg(feature = "std")]
+pub fn ircat_with_palette<R: BufRead, W: Write>(
+    reader: R,
+    writer: &mut W,
+    palette: &Palette,
+) -> Result<u64> {
+    IrcatBuilder::default()
+        .palette(palette.clone())
+        .run(reader, writer)
 }
 
-impl Filter for IRCatFilter {
-    fn init() -> Self {
-        Self {
-            state: IRCatState::Normal,
-            in_color: false,
-        }
-    }
-
-    fn filter(&mut self, input: &[u8], output: &mut Vec<u8>) {
-        output.reserve(input.len());
-        for c in input {
-            match self.state {
-                IRCatState::Normal => match c {
-                    b'\x03' => {
-                        self.state = IRCatState::Start;
-                    }
-                    b'\n' => {
-                        if self.in_color {
-                            output.extend_from_slice(b"\x1b[39m\x1b[49m");
-                            self.in_color = false;
-                        }
-                        output.push(*c);
-                    }
-                    _ => output.push(*c),
-                },
-
-                IRCatState::Start => match c {
-                    b'0'..=b'9' => {
-                        self.in_color = true;
-                        self.state = IRCatState::Foreground1(c - b'0');
-                    }
-                    _ => {
-                        if self.in_color {
-                            self.in_color = false;
-                            output.extend_from_slice(b"\x1b[39m\x1b[49m");
-                        }
-                        output.push(*c);
-                        self.state = IRCatState::Normal;
-                    }
-                },
+/// Like [`ircat`] but choose the kind of ANSI color sequences with `color_mode`.
+///
+/// # Errors
+///
+/// This function will return an error if any call to [`read`] or [`write`] returns an error.
+///
+/// [`read`]: std::io::Read::read
+/// [`write`]: Write::write
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::BufReader;
+/// #
+/// # use ircat::ircat_with_color_mode;
+/// # use ircat::ColorMode;
+/// #
+/// let mut writer = Vec::new();
+/// ircat_with_color_mode(
+///     &mut BufReader::new(b"\x037orange\n".as_ref()),
+///     &mut writer,
+///     ColorMode::TrueColor,
+/// )?;
+/// assert_eq!(writer, b"\x1b[38;2;252;127;0morange\x1b[39m\n");
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+pub fn ircat_with_color_mode<R: BufRead, W: Write>(
+    reader: R,
+    writer: &mut W,
+    color_mode: ColorMode,
+) -> Result<u64> {
+    IrcatBuilder::default()
+        .color_mode(color_mode)
+        .run(reader, writer)
+}
 
-                IRCatState::Foreground1(mut fg_color) => match c {
-                    b'0'..=b'9' => {
-                        fg_color = fg_color * 10 + c - b'0';
-                        output_color(output, true, fg_color);
-                        self.state = IRCatState::Foreground2;
-                    }
-                    b',' => {
-                        output_color(output, true, fg_color);
-                        self.state = IRCatState::Comma;
-                    }
-                    _ => {
-                        output_color(output, true, fg_color);
-                        output.push(*c);
-                        self.state = IRCatState::Normal;
-                    }
-                },
+/// Like [`ircat`] but flush `writer` after every line.
+///
+/// Each line reaches the writer as soon as its `\n` is read which suits live tailing where
+/// [`ircat`] may hold output back until its buffers fill. The extra flushes, and writes as small
+/// as a line, cost throughput so prefer [`ircat`] for whole files.
+///
+/// On success returns the number of bytes written to `writer`.
+///
+/// # Errors
+///
+/// This function will return an error if any call to [`read`], [`write`], or [`flush`] returns an
+/// error.
+///
+/// [`read`]: std::io::Read::read
+/// [`write`]: Write::write
+/// [`flush`]: Write::flush
+#[cfg(feature = "std")]
+pub fn ircat_line_buffered<R: BufRead, W: Write>(mut reader: R, writer: &mut W) -> Result<u64> {
+    let mut filter = IrcatFilter::init();
+    let mut output = Vec::new();
+    let mut written = 0;
+    loop {
+        let buffer = match reader.fill_buf() {
+            Ok(buffer) => buffer,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let eof = buffer.is_empty();
+        let line_end = buffer.iter().position(|c| *c == b'\n');
+        let len = line_end.map_or(buffer.len(), |i| i + 1);
+        if eof {
+            filter.finish(&mut output);
+        } else {
+            filter.filter(&buffer[..len], &mut output);
+        }
+        reader.consume(len);
 
-                IRCatState::Foreground2 => match c {
-                    b',' => self.state = IRCatState::Comma,
-                    _ => {
-                        output.push(*c);
-                        self.state = IRCatState::Normal;
-                    }
-                },
+        writer.write_all(&output)?;
+        written += u64::try_from(output.len()).unwrap_or(u64::MAX);
+        output.clear();
+        if eof {
+            writer.flush()?;
+            return Ok(written);
+        }
+        if line_end.is_some() {
+            writer.flush()?;
+        }
+    }
+}
 
-                IRCatState::Comma => match c {
-                    b'0'..=b'9' => self.state = IRCatState::Background1(c - b'0'),
-                    _ => {
-                        output.push(b',');
-                        output.push(*c);
-                        self.state = IRCatState::Normal;
-                    }
-                },
+/// Like [`ircat`] but fail on malformed or unknown codes instead of printing them as text.
+///
+/// Each chunk read is checked like [`validate`] before it is translated so output stops short of
+/// the chunk holding the first bad code. The offset in the error counts from the start of
+/// `reader`.
+///
+/// On success returns the number of bytes written to `writer`.
+///
+/// # Errors
+///
+/// This function will return [`IrcatError::Io`] if any call to [`read`] or [`write`] returns an
+/// error and [`IrcatError::Format`] at the first malformed or unknown code.
+///
+/// [`read`]: std::io::Read::read
+/// [`write`]: Write::write
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::BufReader;
+/// #
+/// # use ircat::ircat_strict;
+/// # use ircat::FormatError;
+/// # use ircat::IrcatError;
+/// #
+/// let mut writer = Vec::new();
+/// ircat_strict(BufReader::new(b"\x034red".as_ref()), &mut writer)?;
+/// assert_eq!(writer, b"\x1b[31mred\x1b[39m");
+///
+/// let result = ircat_strict(BufReader::new(b"\x04ff red".as_ref()), &mut Vec::new());
+/// assert!(matches!(
+///     result,
+///     Err(IrcatError::Format(FormatError::IncompleteHexColor { offset: 0 })),
+/// ));
+/// # Ok::<(), IrcatError>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn ircat_strict<R: BufRead, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+) -> result::Result<u64, IrcatError> {
+    let mut validator = Validator::new();
+    let mut filter = IrcatFilter::init();
+    let mut output = Vec::new();
+    let mut written = 0;
+    loop {
+        let buffer = match reader.fill_buf() {
+            Ok(buffer) => buffer,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if buffer.is_empty() {
+            validator.finish()?;
+            filter.finish(&mut output);
+        } else {
+            validator.feed(buffer)?;
+            filter.filter(buffer, &mut output);
+        }
+        let len = buffer.len();
+        reader.consume(len);
 
-                IRCatState::Background1(mut bg_color) => match c {
-                    b'0'..=b'9' => {
-                        bg_color = bg_color * 10 + c - b'0';
-                        output_color(output, false, bg_color);
-                        self.state = IRCatState::Normal;
-                    }
-                    _ => {
-                        output_color(output, false, bg_color);
-                        output.push(*c);
-                        self.state = IRCatState::Normal;
-                    }
-                },
-            }
+        writer.write_all(&output)?;
+        written += u64::try_from(output.len()).unwrap_or(u64::MAX);
+        output.clear();
+        if len == 0 {
+            return Ok(written);
         }
     }
 }
 
-fn output_color(output: &mut Vec<u8>, foreground: bool, color: u8) {
-    output.extend_from_slice(b"\x1b[");
-    output.push(if foreground { b'3' } else { b'4' });
-    output.extend_from_slice(lookup_irc_color(color).as_bytes());
-    output.push(b'm');
+/// Like [`ircat`] but also count the formatting codes translated.
+///
+/// On success returns the number of bytes written to `writer` and the counts.
+///
+/// # Errors
+///
+/// This function will return an error if any call to [`read`] or [`write`] returns an error.
+///
+/// [`read`]: std::io::Read::read
+/// [`write`]: Write::write
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::BufReader;
+/// #
+/// # use ircat::ircat_with_stats;
+/// #
+/// let mut writer = Vec::new();
+/// let (_, stats) = ircat_with_stats(&mut BufReader::new(b"\x034,2red\x03".as_ref()), &mut writer)?;
+/// assert_eq!((stats.foreground, stats.background, stats.resets), (1, 1, 1));
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+pub fn ircat_with_stats<R: BufRead, W: Write>(reader: R, writer: &mut W) -> Result<(u64, Stats)> {
+    let mut reader = BufFilter::with_filter(reader, IrcatFilter::init());
+    let written = io::copy(&mut reader, writer)?;
+    Ok((written, reader.get_filter().stats()))
+}
+
+/// Like [`ircat`] unless the `NO_COLOR` environment variable is set to a non-empty value in which
+/// case like [`strip`].
+///
+/// See <https://no-color.org/>. The environment is only checked once per call so the whole stream
+/// is either translated or stripped. The other functions in this crate ignore `NO_COLOR`.
+///
+/// # Errors
+///
+/// This function will return an error if any call to [`read`] or [`write`] returns an error.
+///
+/// [`read`]: std::io::Read::read
+/// [`write`]: Write::write
+#[cfg(feature = "std")]
+pub fn ircat_auto<R: BufRead, W: Write>(reader: R, writer: &mut W) -> Result<u64> {
+    ircat_no_color(reader, writer, env::var_os("NO_COLOR").as_deref())
 }
 
-fn lookup_irc_color(color: u8) -> &'static str {
-    match color {
-        0 => "7",
-        1 => "8;5;235",
-        2 => "4",
-        3 => "2",
-        4 => "1",
-        5 => "8;5;52",
-        6 => "5",
-        7 => "8;5;209",
-        8 => "3",
-        9 => "8;5;47",
-        10 => "6",
-        11 => "6",
-        12 => "8;5;56",
-        13 => "8;5;200",
-        14 => "8;5;241",
-        15 => "7",
-        _ => todo!("missing color: {}", color),
+/// Like [`ircat_auto`] with `no_color` as the value of `NO_COLOR`.
+#[cfg(feature = "std")]
+fn ircat_no_color<R: BufRead, W: Write>(
+    reader: R,
+    writer: &mut W,
+    no_color: Option<&OsStr>,
+) -> Result<u64> {
+    if no_color.is_some_and(|value| !value.is_empty()) {
+        strip(reader, writer)
+    } else {
+        ircat(reader, writer)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::io::BufReader;
+/// Stream bytes from `reader` to `writer` while removing all IRC formatting.
+///
+/// On success returns the number of bytes written to `writer`.
+///
+/// # Errors
+///
+/// This function will return an error if any call to [`read`] or [`write`] returns an error.
+///
+/// [`read`]: std::io::Read::read
+/// [`write`]: Write::write
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::BufReader;
+/// #
+/// # use ircat::strip;
+/// #
+/// let mut writer = Vec::new();
+/// strip(&mut BufReader::new(b"Colors \x034red \x02bold\x02\n".as_ref()), &mut writer)?;
+/// assert_eq!(writer, b"Colors red bold\n");
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+pub fn strip<R: BufRead, W: Write>(reader: R, writer: &mut W) -> Result<u64> {
+    IrcatBuilder::default()
+        .output(OutputFormat::Strip)
+        .run(reader, writer)
+}
 
-    use crate::ircat;
+/// Stream bytes from `reader` to `writer` while translating IRC color codes into HTML.
+///
+/// Formatted text is wrapped in `<span>` elements with inline styles and HTML special characters
+/// are escaped.
+///
+/// On success returns the number of bytes written to `writer`.
+///
+/// # Errors
+///
+/// This function will return an error if any call to [`read`] or [`write`] returns an error.
+///
+/// [`read`]: std::io::Read::read
+/// [`write`]: Write::write
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::BufReader;
+/// #
+/// # use ircat::ircat_html;
+/// #
+/// let mut writer = Vec::new();
+/// ircat_html(&mut BufReader::new(b"\x034red\x03 & plain\n".as_ref()), &mut writer)?;
+/// assert_eq!(writer, b"<span style=\"color:#ff0000\">red</span> &amp; plain\n");
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+pub fn ircat_html<R: BufRead, W: Write>(reader: R, writer: &mut W) -> Result<u64> {
+    IrcatBuilder::default()
+        .output(OutputFormat::Html)
+        .run(reader, writer)
+}
 
-    macro_rules! tests {
-        ($(($name: ident, $input: expr, $expected: expr),)*) => {
-            $(
-                #[test]
-                fn $name() {
-                    let mut result = Vec::new();
-                    ircat(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
-                    assert_eq!(result, $expected);
-                }
-            )*
+/// Stream bytes from `reader` to `writer` while translating IRC color codes into Pango markup.
+///
+/// Colors become `<span>` elements and attributes become `<b>`, `<i>`, `<u>`, `<s>`, and `<tt>`
+/// tags. Markup special characters are escaped.
+///
+/// On success returns the number of bytes written to `writer`.
+///
+/// # Errors
+///
+/// This function will return an error if any call to [`read`] or [`write`] returns an error.
+///
+/// [`read`]: std::io::Read::read
+/// [`write`]: Write::write
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::BufReader;
+/// #
+/// # use ircat::ircat_pango;
+/// #
+/// let mut writer = Vec::new();
+/// ircat_pango(&mut BufReader::new(b"\x02\x034red\x03 & bold\n".as_ref()), &mut writer)?;
+/// assert_eq!(writer, b"<span foreground=\"#ff0000\"><b>red</b></span><b> &amp; bold</b>\n");
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+pub fn ircat_pango<R: BufRead, W: Write>(reader: R, writer: &mut W) -> Result<u64> {
+    IrcatBuilder::default()
+        .output(OutputFormat::Pango)
+        .run(reader, writer)
+}
+
+/// Return an iterator over the lines of `reader` with IRC color codes translated into ANSI ones.
+///
+/// Like [`BufRead::lines`] the line endings (`\n` or `\r\n`) are removed. Each line is translated
+/// independently and ends with any resets needed so lines can be rendered on their own.
+///
+/// # Errors
+///
+/// The iterator yields an error if any call to [`read`] returns an error or if a line is not valid
+/// UTF-8.
+///
+/// [`read`]: std::io::Read::read
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::BufReader;
+/// #
+/// # use ircat::ircat_lines;
+/// #
+/// let input = b"\x034red\nplain\r\n";
+/// let lines = ircat_lines(BufReader::new(input.as_ref())).collect::<std::io::Result<Vec<_>>>()?;
+/// assert_eq!(lines, ["\x1b[31mred\x1b[39m", "plain"]);
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+pub fn ircat_lines<R: BufRead>(reader: R) -> impl Iterator<Item = Result<String>> {
+    reader.split(b'\n').map(|line| {
+        let mut line = line?;
+        if line.last() == Some(&b'\r') {
+            line.pop();
         }
-    }
+        String::from_utf8(ircat_bytes(&line)).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    })
+}
 
-    tests!(
-        (none, b"foo bar", b"foo bar"),
-        (empty, b"", b""),
-        (
-            newline,
-            b"\x032blue\nnone\n",
-            b"\x1b[34mblue\x1b[39m\x1b[49m\nnone\n"
-        ),
-        (
-            stop_color,
-            b"\x032blue\x03none\n",
-            b"\x1b[34mblue\x1b[39m\x1b[49mnone\n"
-        ),
-        (two_digit_fg, b"\x0310cyan", b"\x1b[36mcyan"),
-        (number_following, b"\x03002white", b"\x1b[37m2white"),
-        (bg, b"\x032,3test", b"\x1b[34m\x1b[42mtest"),
-        (bg_two_digit, b"\x032,10test", b"\x1b[34m\x1b[46mtest"),
-        (no_fg, b"\x03,2invalid", b",2invalid"),
-        (
-            no_fg_in_color,
-            b"\x032blue\x03,2invalid",
-            b"\x1b[34mblue\x1b[39m\x1b[49m,2invalid"
-        ),
-        (
-            double_reset,
-            b"\x032blue\x03none\x03none",
-            b"\x1b[34mblue\x1b[39m\x1b[49mnonenone"
-        ),
-    );
+/// Translate IRC color codes in `input` into ANSI ones.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::ircat_str;
+/// #
+/// assert_eq!(
+///     ircat_str("Colors \x034red \x033green \x032blue\n"),
+///     "Colors \x1b[31mred \x1b[32mgreen \x1b[34mblue\x1b[39m\n",
+/// );
+/// ```
+#[must_use]
+pub fn ircat_str(input: &str) -> String {
+    let output = ircat_bytes(input.as_bytes());
+    // Only ASCII bytes are added or removed so the output remains valid UTF-8.
+    String::from_utf8(output).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+}
 
-    #[test]
-    #[should_panic]
-    fn unknown_color() {
-        ircat(&mut BufReader::new(b"\x0316text".as_ref()), &mut Vec::new()).unwrap();
+/// Translate IRC color codes in `input` into ANSI ones.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::ircat_bytes;
+/// #
+/// assert_eq!(
+///     ircat_bytes(b"Colors \x034red \x033green \x032blue\n"),
+///     b"Colors \x1b[31mred \x1b[32mgreen \x1b[34mblue\x1b[39m\n",
+/// );
+/// ```
+#[must_use]
+pub fn ircat_bytes(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    IrcatFilter::init().translate(input, &mut output);
+    output
+}
+
+/// Translate IRC color codes in `input` into ANSI ones, borrowing `input` if it has none.
+///
+/// Most lines carry no formatting at all, which this finds with a quick scan and returns as is
+/// without allocating.
+///
+/// # Examples
+///
+/// ```
+/// # use std::borrow::Cow;
+/// #
+/// # use ircat::ircat_cow;
+/// #
+/// assert_eq!(ircat_cow(b"plain\n"), Cow::Borrowed(b"plain\n"));
+/// assert_eq!(ircat_cow(b"\x034red"), Cow::<[u8]>::Owned(b"\x1b[31mred\x1b[39m".to_vec()));
+/// ```
+#[must_use]
+pub fn ircat_cow(input: &[u8]) -> Cow<'_, [u8]> {
+    if parser::has_formatting(input) {
+        Cow::Owned(ircat_bytes(input))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Count the bytes [`ircat_bytes`] produces for `input` without producing them.
+///
+/// This lets a caller allocate a buffer of exactly the right size up front. The translation is
+/// rendered a little at a time into a small buffer that is only counted.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::ansi_output_len;
+/// # use ircat::ircat_bytes;
+/// #
+/// let input = b"\x034,2red\x02bold";
+/// assert_eq!(ansi_output_len(input), ircat_bytes(input).len());
+/// ```
+#[must_use]
+pub fn ansi_output_len(input: &[u8]) -> usize {
+    /// The most text rendered at once.
+    const TEXT_CHUNK: usize = 64;
+
+    let IrcatFilter {
+        mut decoder,
+        mut stages,
+        mut renderer,
+        ..
+    } = IrcatFilter::init();
+    let mut scratch = Vec::new();
+    let mut len = 0;
+    let mut count = |event: Event<'_>| {
+        let mut render = |event: Event<'_>| {
+            stages.render(event, &mut renderer, &mut scratch);
+            len += scratch.len();
+            scratch.clear();
+        };
+        match event {
+            Event::Text(text) => text
+                .chunks(TEXT_CHUNK)
+                .for_each(|text| render(Event::Text(text))),
+            _ => render(event),
+        }
+    };
+    decoder.parse(input, &mut count);
+    decoder.finish(&mut count);
+    stages.finish(&mut renderer, &mut scratch);
+    renderer.finish(&mut scratch);
+    len + scratch.len()
+}
+
+/// A [`Filter`] translating IRC formatting as configured by an [`IrcatBuilder`].
+#[derive(Debug)]
+pub struct IrcatFilter {
+    decoder: Decoder,
+    stages: Stages,
+    renderer: Renderer,
+    stats: Stats,
+}
+
+impl IrcatFilter {
+    /// Get the counts of codes translated so far.
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Get the display column the output has reached on its current line.
+    ///
+    /// Text is counted as it is shown so formatting codes and escape sequences take no columns
+    /// while wide characters take two. This is `None` unless columns are tracked, see
+    /// [`IrcatBuilder::track_columns`].
+    #[must_use]
+    pub fn column(&self) -> Option<usize> {
+        self.stages.columns.as_ref().map(Columns::column)
+    }
+
+    /// Take the display widths of the lines output since the last call, counted like
+    /// [`column`](Self::column) does.
+    ///
+    /// A line ends at a `\n` while a `\r` only goes back to its start, so a line is as wide as
+    /// the furthest column it reached. [`finish`](Filter::finish) also ends a line with anything
+    /// on it. Nothing is returned unless columns are tracked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ircat::Filter;
+    /// # use ircat::IrcatBuilder;
+    /// #
+    /// let mut filter = IrcatBuilder::default().track_columns(true).build();
+    /// let mut output = Vec::new();
+    /// filter.filter(b"\x034,2red\x03\nand \x02bold", &mut output);
+    /// assert_eq!(filter.column(), Some(8));
+    /// filter.finish(&mut output);
+    /// assert_eq!(filter.line_widths().collect::<Vec<_>>(), [3, 8]);
+    /// ```
+    pub fn line_widths(&mut self) -> impl Iterator<Item = usize> + '_ {
+        self.stages.columns.iter_mut().flat_map(Columns::lines)
+    }
+
+    /// Return to the initial state discarding any pending code and active formatting without
+    /// emitting anything.
+    ///
+    /// Call this between logically independent inputs fed through the same filter, such as
+    /// concatenated log files, so formatting left open at the end of one does not carry over to
+    /// the next. Use [`finish`] instead to close the formatting in the output. The configuration
+    /// and [`stats`] are kept.
+    ///
+    /// [`finish`]: Filter::finish
+    /// [`stats`]: IrcatFilter::stats
+    pub fn reset(&mut self) {
+        self.decoder.reset();
+        self.stages.reset();
+        self.renderer.reset();
+    }
+
+    /// Translate a complete message appending the result to `output`.
+    ///
+    /// This is [`filter`] followed by [`finish`] so no formatting carries over to the next
+    /// message. `output` is only appended to, never cleared, so a single buffer can be reused
+    /// across messages by clearing it in between without allocating once it has grown large
+    /// enough.
+    ///
+    /// [`filter`]: Filter::filter
+    /// [`finish`]: Filter::finish
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ircat::Filter;
+    /// # use ircat::IrcatFilter;
+    /// #
+    /// let mut filter = IrcatFilter::init();
+    /// let mut output = Vec::new();
+    /// for (message, expected) in [
+    ///     (b"\x034red".as_ref(), b"\x1b[31mred\x1b[39m".as_ref()),
+    ///     (b"plain", b"plain"),
+    /// ] {
+    ///     output.clear();
+    ///     filter.translate(message, &mut output);
+    ///     assert_eq!(output, expected);
+    /// }
+    /// ```
+    pub fn translate(&mut self, message: &[u8], output: &mut Vec<u8>) {
+        self.filter(message, output);
+        self.finish(output);
+    }
+
+    /// Translate a complete message without closing any formatting it leaves active.
+    ///
+    /// Returns the translation and whether formatting is still active at its end. Any code cut
+    /// off by the end of the message is completed but nothing is reset so the caller can decide
+    /// whether to close it with [`finish`] or let it carry over to the next message.
+    ///
+    /// [`finish`]: Filter::finish
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ircat::Filter;
+    /// # use ircat::IrcatFilter;
+    /// #
+    /// let mut filter = IrcatFilter::init();
+    /// assert_eq!(filter.translate_message(b"\x02bold\x02"), (b"\x1b[1mbold\x1b[22m".to_vec(), false));
+    /// let (mut output, open) = filter.translate_message(b"\x034red");
+    /// assert_eq!((output.as_slice(), open), (b"\x1b[31mred".as_ref(), true));
+    /// filter.finish(&mut output);
+    /// assert_eq!(output, b"\x1b[31mred\x1b[39m");
+    /// ```
+    #[must_use]
+    pub fn translate_message(&mut self, message: &[u8]) -> (Vec<u8>, bool) {
+        let mut output = Vec::new();
+        self.filter(message, &mut output);
+        self.decoder.finish(|event| {
+            self.stats.count(event);
+            self.stages.render(event, &mut self.renderer, &mut output);
+        });
+        self.stages.finish(&mut self.renderer, &mut output);
+        (output, self.renderer.is_active())
+    }
+}
+
+/// The optional stages events pass through between the decoder and the renderer.
+#[derive(Debug)]
+struct Stages {
+    /// The masks of the attributes whose codes are dropped.
+    disabled: u8,
+    ctcp: Option<Ctcp>,
+    strip_controls: bool,
+    hyperlinks: Option<Hyperlinks>,
+    highlighter: Option<Highlighter>,
+    timestamps: Option<Timestamps>,
+    wrap: Option<Wrap>,
+    tabs: Option<Tabs>,
+    columns: Option<Columns>,
+}
+
+impl Stages {
+    fn render(&mut self, event: Event<'_>, renderer: &mut Renderer, output: &mut Vec<u8>) {
+        if let Event::Toggle(attribute) = event {
+            if self.disabled & attribute.mask() != 0 {
+                return;
+            }
+        }
+        let mut render = |event: Event<'_>| {
+            render_expanded(&mut self.tabs, &mut self.columns, event, renderer, output);
+        };
+        let mut render = |event: Event<'_>| match &mut self.wrap {
+            Some(wrap) => wrap.process(event, &mut render),
+            None => render(event),
+        };
+        let mut render = |event: Event<'_>| match &mut self.timestamps {
+            Some(timestamps) => timestamps.process(event, &mut render),
+            None => render(event),
+        };
+        let mut render = |event: Event<'_>| match &mut self.highlighter {
+            Some(highlighter) => highlighter.process(event, &mut render),
+            None => render(event),
+        };
+        let mut render = |event: Event<'_>| match &mut self.hyperlinks {
+            Some(hyperlinks) => hyperlinks.process(event, &mut render),
+            None => render(event),
+        };
+        let mut render = |event: Event<'_>| {
+            if self.strip_controls {
+                controls::process(event, &mut render);
+            } else {
+                render(event);
+            }
+        };
+        match &mut self.ctcp {
+            Some(ctcp) => ctcp.process(event, &mut render),
+            None => render(event),
+        }
+    }
+
+    /// Render anything the stages held back.
+    fn finish(&mut self, renderer: &mut Renderer, output: &mut Vec<u8>) {
+        // Take the stage out while the rest of the stages render what it held back.
+        if let Some(mut ctcp) = self.ctcp.take() {
+            ctcp.finish(&mut |event| self.render(event, renderer, output));
+            self.ctcp = Some(ctcp);
+        }
+        let mut render = |event: Event<'_>| {
+            render_expanded(&mut self.tabs, &mut self.columns, event, renderer, output);
+        };
+        let mut render = |event: Event<'_>| match &mut self.wrap {
+            Some(wrap) => wrap.process(event, &mut render),
+            None => render(event),
+        };
+        let mut render = |event: Event<'_>| match &mut self.timestamps {
+            Some(timestamps) => timestamps.process(event, &mut render),
+            None => render(event),
+        };
+        if let Some(hyperlinks) = &mut self.hyperlinks {
+            hyperlinks.finish(&mut |event| match &mut self.highlighter {
+                Some(highlighter) => highlighter.process(event, &mut render),
+                None => render(event),
+            });
+        }
+        if let Some(highlighter) = &mut self.highlighter {
+            highlighter.finish(&mut render);
+        }
+        if let Some(wrap) = &mut self.wrap {
+            wrap.finish(&mut |event| {
+                render_expanded(&mut self.tabs, &mut self.columns, event, renderer, output);
+            });
+            wrap.reset();
+        }
+        if let Some(timestamps) = &mut self.timestamps {
+            timestamps.reset();
+        }
+        if let Some(tabs) = &mut self.tabs {
+            *tabs = Tabs::new(tabs.width());
+        }
+        if let Some(columns) = &mut self.columns {
+            columns.finish();
+        }
+    }
+
+    fn reset(&mut self) {
+        if let Some(ctcp) = &mut self.ctcp {
+            *ctcp = Ctcp::new();
+        }
+        if let Some(hyperlinks) = &mut self.hyperlinks {
+            *hyperlinks = Hyperlinks::new();
+        }
+        if let Some(highlighter) = &mut self.highlighter {
+            highlighter.reset();
+        }
+        if let Some(timestamps) = &mut self.timestamps {
+            timestamps.reset();
+        }
+        if let Some(wrap) = &mut self.wrap {
+            wrap.reset();
+        }
+        if let Some(tabs) = &mut self.tabs {
+            *tabs = Tabs::new(tabs.width());
+        }
+        if let Some(columns) = &mut self.columns {
+            columns.reset();
+        }
+    }
+}
+
+/// Render `event` after the last stage, expanding its tabs if asked to and counting the columns
+/// rendered if asked to.
+///
+/// Tabs are expanded after wrapping so their stops are counted from the start of every line.
+fn render_expanded(
+    tabs: &mut Option<Tabs>,
+    columns: &mut Option<Columns>,
+    event: Event<'_>,
+    renderer: &mut Renderer,
+    output: &mut Vec<u8>,
+) {
+    let mut render = |event: Event<'_>| {
+        if let Some(columns) = columns {
+            columns.count(event);
+        }
+        renderer.render(event, output);
+    };
+    match tabs {
+        Some(tabs) => tabs.process(event, &mut render),
+        None => render(event),
+    }
+}
+
+#[derive(Debug)]
+enum Decoder {
+    Irc(Parser),
+    WeeChat(WeeChatParser),
+}
+
+impl Decoder {
+    fn parse(&mut self, input: &[u8], handle: impl FnMut(Event<'_>)) {
+        match self {
+            Self::Irc(parser) => parser.parse(input, handle),
+            Self::WeeChat(parser) => parser.parse(input, handle),
+        }
+    }
+
+    fn finish(&mut self, handle: impl FnMut(Event<'_>)) {
+        match self {
+            Self::Irc(parser) => parser.finish(handle),
+            Self::WeeChat(parser) => parser.finish(handle),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Self::Irc(parser) => parser.reset(),
+            Self::WeeChat(parser) => *parser = WeeChatParser::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Renderer {
+    Ansi(Box<Ansi>),
+    Html(Html),
+    Irc(Irc),
+    Pango(Pango),
+    Strip,
+}
+
+impl Renderer {
+    fn render(&mut self, event: Event<'_>, output: &mut Vec<u8>) {
+        match self {
+            Self::Ansi(ansi) => ansi.render(event, output),
+            Self::Html(html) => html.render(event, output),
+            Self::Irc(irc) => irc.render(event, output),
+            Self::Pango(pango) => pango.render(event, output),
+            Self::Strip => strip::render(event, output),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Self::Ansi(ansi) => ansi.reset(),
+            Self::Html(html) => *html = Html::new(),
+            Self::Irc(irc) => *irc = Irc::new(),
+            Self::Pango(pango) => *pango = Pango::new(),
+            Self::Strip => {}
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        match self {
+            Self::Ansi(ansi) => ansi.is_active(),
+            Self::Html(html) => html.is_active(),
+            Self::Irc(irc) => irc.is_active(),
+            Self::Pango(pango) => pango.is_active(),
+            Self::Strip => false,
+        }
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) {
+        match self {
+            Self::Ansi(ansi) => ansi.finish(output),
+            Self::Html(html) => html.finish(output),
+            Self::Irc(irc) => irc.finish(),
+            Self::Pango(pango) => pango.finish(output),
+            Self::Strip => {}
+        }
+    }
+}
+
+/// How many bytes a color code may add to the output beyond its own length.
+///
+/// This covers a code setting both colors such as `\x0304,01` rendered as 256 colors.
+const COLOR_EXPANSION: usize = 16;
+
+impl Filter for IrcatFilter {
+    fn init() -> Self {
+        IrcatBuilder::default().build()
+    }
+
+    fn filter(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        // Color codes are where the output grows most so leave room for their expansion up front
+        // rather than reallocating as they are rendered. Counting in bytes a chunk at a time keeps
+        // this cheap next to the parse.
+        let colors: usize = input
+            .chunks(usize::from(u8::MAX))
+            .map(|chunk| {
+                let colors: u8 = chunk
+                    .iter()
+                    .map(|c| u8::from(matches!(c, b'\x03' | b'\x04')))
+                    .sum();
+                usize::from(colors)
+            })
+            .sum();
+        output.reserve(input.len() + colors * COLOR_EXPANSION);
+        self.decoder.parse(input, |event| {
+            self.stats.count(event);
+            self.stages.render(event, &mut self.renderer, output);
+        });
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) {
+        self.decoder.finish(|event| {
+            self.stats.count(event);
+            self.stages.render(event, &mut self.renderer, output);
+        });
+        self.stages.finish(&mut self.renderer, output);
+        self.renderer.finish(output);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::borrow::Cow;
+    use std::cmp;
+    use std::ffi::OsStr;
+    use std::fmt::Write as _;
+    use std::fs;
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::io::ErrorKind;
+    use std::io::Read;
+    use std::io::Result;
+    use std::io::Write;
+    use std::path::Path;
+    use std::result;
+
+    use crate::ansi_output_len;
+    use crate::color_histogram;
+    use crate::ircat;
+    use crate::ircat_bytes;
+    use crate::ircat_cow;
+    use crate::ircat_line_buffered;
+    use crate::ircat_lines;
+    use crate::ircat_no_color;
+    use crate::ircat_read;
+    use crate::ircat_str;
+    use crate::ircat_strict;
+    use crate::ircat_with_color_mode;
+    use crate::ircat_with_palette;
+    use crate::ircat_with_stats;
+    use crate::Attribute;
+    use crate::Bell;
+    use crate::BufFilter;
+    use crate::ColorMode;
+    use crate::Filter;
+    use crate::FilterWriter;
+    use crate::FormatError;
+    use crate::IrcatBuilder;
+    use crate::IrcatError;
+    use crate::IrcatFilter;
+    use crate::OutputFormat;
+    use crate::Palette;
+    use crate::Stats;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let mut result = Vec::new();
+                    ircat(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    // Feed one byte at a time so every code is split across reads.
+                    let mut result = Vec::new();
+                    ircat(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", b"foo bar"),
+        (empty, b"", b""),
+        (
+            long_text,
+            b"some plain text\x02 then bold text\nand more",
+            b"some plain text\x1b[1m then bold text\x1b[22m\nand more"
+        ),
+        (
+            newline,
+            b"\x032blue\nnone\n",
+            b"\x1b[34mblue\x1b[39m\nnone\n"
+        ),
+        (
+            stop_color,
+            b"\x032blue\x03none\n",
+            b"\x1b[34mblue\x1b[39mnone\n"
+        ),
+        (two_digit_fg, b"\x0310cyan", b"\x1b[36mcyan\x1b[39m"),
+        (number_following, b"\x03002white", b"\x1b[37m2white\x1b[39m"),
+        (leading_zeros, b"\x030002", b"\x1b[37m02\x1b[39m"),
+        (leading_zeros_comma, b"\x03001,2", b"\x1b[37m1,2\x1b[39m"),
+        (
+            leading_zeros_three_comma,
+            b"\x03000,1",
+            b"\x1b[37m0,1\x1b[39m"
+        ),
+        (
+            leading_zeros_bg,
+            b"\x0301,002x",
+            b"\x1b[38;5;235m\x1b[47m2x\x1b[39m\x1b[49m"
+        ),
+        (
+            leading_zeros_both,
+            b"\x0300,0000",
+            b"\x1b[37m\x1b[47m00\x1b[39m\x1b[49m"
+        ),
+        (
+            bg_leading_zero,
+            b"\x034,05",
+            b"\x1b[31m\x1b[48;5;52m\x1b[39m\x1b[49m"
+        ),
+        (
+            bg_number_following,
+            b"\x0399,099",
+            b"\x1b[39m\x1b[48;5;47m9\x1b[49m"
+        ),
+        (
+            bg_two_digit_number_following,
+            b"\x032,100",
+            b"\x1b[34m\x1b[46m0\x1b[39m\x1b[49m"
+        ),
+        (
+            bg_one_digit_text_following,
+            b"\x032,1x",
+            b"\x1b[34m\x1b[48;5;235mx\x1b[39m\x1b[49m"
+        ),
+        (three_digit_fg, b"\x03100", b"\x1b[36m0\x1b[39m"),
+        (three_digit_fg_text, b"\x03999lives", b"\x1b[39m9lives"),
+        (bg, b"\x032,3test", b"\x1b[34m\x1b[42mtest\x1b[39m\x1b[49m"),
+        (
+            bg_only,
+            b"\x0399,3test\n",
+            b"\x1b[39m\x1b[42mtest\x1b[49m\n"
+        ),
+        (
+            bg_after_default_fg,
+            b"\x034red\x0399,4text\x0399,12blue",
+            b"\x1b[31mred\x1b[39m\x1b[41mtext\x1b[48;5;56mblue\x1b[49m"
+        ),
+        (
+            bg_two_digit,
+            b"\x032,10test",
+            b"\x1b[34m\x1b[46mtest\x1b[39m\x1b[49m"
+        ),
+        (no_fg, b"\x03,2invalid", b",2invalid"),
+        (
+            no_fg_in_color,
+            b"\x032blue\x03,2invalid",
+            b"\x1b[34mblue\x1b[39m,2invalid"
+        ),
+        (
+            no_fg_in_color_with_bg,
+            b"\x032,3text\x03,9",
+            b"\x1b[34m\x1b[42mtext\x1b[39m\x1b[49m,9"
+        ),
+        (
+            double_reset,
+            b"\x032blue\x03none\x03none",
+            b"\x1b[34mblue\x1b[39mnonenone"
+        ),
+        (reset_without_color, b"\x03\x03plain", b"plain"),
+        (only_reset, b"\x03", b""),
+        (reset_line_ends, b"a\x03\nb\x03\r\n\x03", b"a\nb\r\n"),
+        (reset_all_without_color, b"\x0f\x03plain\x0f\n", b"plain\n"),
+        (hex_reset_without_color, b"\x04plain\x04", b"plain"),
+        (bold, b"\x02bold\x02normal", b"\x1b[1mbold\x1b[22mnormal"),
+        (
+            bold_newline,
+            b"\x02bold\nnormal\n",
+            b"\x1b[1mbold\x1b[22m\nnormal\n"
+        ),
+        (
+            bold_after_color,
+            b"\x034\x02red\n",
+            b"\x1b[31m\x1b[1mred\x1b[39m\x1b[22m\n"
+        ),
+        (italic, b"\x1dfoo\x1d", b"\x1b[3mfoo\x1b[23m"),
+        (italic_newline, b"\x1dfoo\nbar", b"\x1b[3mfoo\x1b[23m\nbar"),
+        (underline, b"\x1ffoo\x1f", b"\x1b[4mfoo\x1b[24m"),
+        (
+            underline_color,
+            b"\x032\x1fblue\n",
+            b"\x1b[34m\x1b[4mblue\x1b[39m\x1b[24m\n"
+        ),
+        (reset, b"\x032blue\x0fplain", b"\x1b[34mblue\x1b[0mplain"),
+        (
+            reset_attributes,
+            b"\x02\x1dfoo\x0fbar\n",
+            b"\x1b[1m\x1b[3mfoo\x1b[0mbar\n"
+        ),
+        (reset_nothing, b"foo\x0fbar", b"foobar"),
+        (
+            reset_toggled_off,
+            b"\x02\x1d\x02foo\x1d\x0fbar",
+            b"\x1b[1m\x1b[3m\x1b[22mfoo\x1b[23mbar"
+        ),
+        (
+            reset_tangled,
+            b"\x02\x1d\x1f\x02\x16foo\x0f\x1dbar\n",
+            b"\x1b[1m\x1b[3m\x1b[4m\x1b[22m\x1b[7mfoo\x1b[0m\x1b[3mbar\x1b[23m\n"
+        ),
+        (
+            reset_twice,
+            b"\x034\x02foo\x0f\x0fbar",
+            b"\x1b[31m\x1b[1mfoo\x1b[0mbar"
+        ),
+        (reverse, b"\x16text\n", b"\x1b[7mtext\x1b[27m\n"),
+        (monospace, b"\x11code\x11", b"code"),
+        (strikethrough, b"\x1efoo\x1e", b"\x1b[9mfoo\x1b[29m"),
+        (
+            strikethrough_newline,
+            b"\x1efoo\nbar",
+            b"\x1b[9mfoo\x1b[29m\nbar"
+        ),
+        (extended_16, b"\x0316text", b"\x1b[38;5;52mtext\x1b[39m"),
+        (extended_50, b"\x0350text", b"\x1b[38;5;127mtext\x1b[39m"),
+        (extended_87, b"\x0387text", b"\x1b[38;5;212mtext\x1b[39m"),
+        (
+            extended_bg,
+            b"\x032,16text",
+            b"\x1b[34m\x1b[48;5;52mtext\x1b[39m\x1b[49m"
+        ),
+        (default_fg, b"\x0399text", b"\x1b[39mtext"),
+        (default_bg, b"\x034,99text", b"\x1b[31m\x1b[49mtext\x1b[39m"),
+        (default_both, b"\x0399,99text", b"\x1b[39m\x1b[49mtext"),
+        (
+            default_fg_bg,
+            b"\x0399,4text",
+            b"\x1b[39m\x1b[41mtext\x1b[49m"
+        ),
+        (repeated_color, b"\x034\x034red", b"\x1b[31mred\x1b[39m"),
+        (
+            consecutive_colors,
+            b"\x034\x033green",
+            b"\x1b[31m\x1b[32mgreen\x1b[39m"
+        ),
+        (
+            consecutive_colors_two_digit,
+            b"\x0304\x0303green",
+            b"\x1b[31m\x1b[32mgreen\x1b[39m"
+        ),
+        (
+            consecutive_colors_bg,
+            b"\x034,2\x033green",
+            b"\x1b[31m\x1b[44m\x1b[32mgreen\x1b[39m\x1b[49m"
+        ),
+        (
+            repeated_bg,
+            b"\x034,2a\x035,2b",
+            b"\x1b[31m\x1b[44ma\x1b[38;5;52mb\x1b[39m\x1b[49m"
+        ),
+        (
+            repeated_after_reset,
+            b"\x034a\x03\x034b",
+            b"\x1b[31ma\x1b[39m\x1b[31mb\x1b[39m"
+        ),
+        (
+            repeated_across_line,
+            b"\x034a\n\x034b",
+            b"\x1b[31ma\x1b[39m\n\x1b[31mb\x1b[39m"
+        ),
+        (repeated_default, b"\x0399\x0399text", b"\x1b[39mtext"),
+        (unterminated, b"\x032blue", b"\x1b[34mblue\x1b[39m"),
+        (
+            unterminated_attributes,
+            b"\x02\x1ffoo",
+            b"\x1b[1m\x1b[4mfoo\x1b[22m\x1b[24m"
+        ),
+        (unterminated_comma, b"\x032,", b"\x1b[34m,\x1b[39m"),
+        (one_digit_comma, b"\x032,x", b"\x1b[34m,x\x1b[39m"),
+        (one_digit_comma_comma, b"\x032,,3", b"\x1b[34m,,3\x1b[39m"),
+        (two_digit_comma, b"\x0310,x", b"\x1b[36m,x\x1b[39m"),
+        (one_digit_reset, b"\x034\x03red", b"\x1b[31m\x1b[39mred"),
+        (two_digit_reset, b"\x0304\x03red", b"\x1b[31m\x1b[39mred"),
+        (comma_reset, b"\x034,\x03red", b"\x1b[31m,\x1b[39mred"),
+        (
+            bg_one_digit_reset,
+            b"\x034,2\x03red",
+            b"\x1b[31m\x1b[44m\x1b[39m\x1b[49mred"
+        ),
+        (
+            one_digit_new_color,
+            b"\x034\x033green",
+            b"\x1b[31m\x1b[32mgreen\x1b[39m"
+        ),
+        (two_digit_comma_end, b"\x0310,", b"\x1b[36m,\x1b[39m"),
+        (
+            two_digit_comma_code,
+            b"\x0310,\x02x",
+            b"\x1b[36m,\x1b[1mx\x1b[39m\x1b[22m"
+        ),
+        (
+            trailing_reset_color,
+            b"\x034red\x03",
+            b"\x1b[31mred\x1b[39m"
+        ),
+        (
+            trailing_reset_color_bg,
+            b"\x034,2red\x03",
+            b"\x1b[31m\x1b[44mred\x1b[39m\x1b[49m"
+        ),
+        (trailing_lone_reset_color, b"plain\x03", b"plain"),
+        (
+            reset_color_keeps_bold,
+            b"\x02\x034bold-red\x03still-bold",
+            b"\x1b[1m\x1b[31mbold-red\x1b[39mstill-bold\x1b[22m"
+        ),
+        (
+            reset_color_keeps_attributes,
+            b"\x1d\x1f\x034,2text\x03still\x1d\x1fplain",
+            b"\x1b[3m\x1b[4m\x1b[31m\x1b[44mtext\x1b[39m\x1b[49mstill\x1b[23m\x1b[24mplain"
+        ),
+        (
+            reset_hex_color_keeps_bold,
+            b"\x02\x04ff0000red\x04still",
+            b"\x1b[1m\x1b[38;2;255;0;0mred\x1b[39mstill\x1b[22m"
+        ),
+        (
+            trailing_background,
+            b"\x034red\x034,2",
+            b"\x1b[31mred\x1b[44m\x1b[39m\x1b[49m"
+        ),
+        (
+            hex,
+            b"\x04ff8000orange",
+            b"\x1b[38;2;255;128;0morange\x1b[39m"
+        ),
+        (
+            hex_bg,
+            b"\x04FF8000,00007ftext",
+            b"\x1b[38;2;255;128;0m\x1b[48;2;0;0;127mtext\x1b[39m\x1b[49m"
+        ),
+        (
+            hex_reset,
+            b"\x04ff0000red\x04plain",
+            b"\x1b[38;2;255;0;0mred\x1b[39mplain"
+        ),
+        (hex_short, b"\x04ff00x", b"ff00x"),
+        (
+            carriage_return,
+            b"\x032blue\r\nnone\r\n",
+            b"\x1b[34mblue\x1b[39m\r\nnone\r\n"
+        ),
+        (
+            carriage_return_attributes,
+            b"\x02\x032bold\rnone",
+            b"\x1b[1m\x1b[34mbold\x1b[39m\x1b[22m\rnone"
+        ),
+        (
+            hex_short_bg,
+            b"\x04ff0000,12x",
+            b"\x1b[38;2;255;0;0m,12x\x1b[39m"
+        ),
+        (hex_comma, b"\x04ff0000,x", b"\x1b[38;2;255;0;0m,x\x1b[39m"),
+        (hex_unterminated, b"\x04ff0", b"ff0"),
+    );
+
+    #[test]
+    fn palette() -> Result<()> {
+        let mut palette = Palette::default();
+        palette.set(4, "8;5;196");
+        palette.set(16, "5");
+        let mut result = Vec::new();
+        ircat_with_palette(
+            &mut BufReader::new(b"\x034,16red\x032blue".as_ref()),
+            &mut result,
+            &palette,
+        )?;
+        assert_eq!(
+            result,
+            b"\x1b[38;5;196m\x1b[45mred\x1b[34mblue\x1b[39m\x1b[49m"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn colorblind_safe() -> Result<()> {
+        let palette = Palette::colorblind_safe();
+        let default = Palette::default();
+        for index in [3, 4] {
+            assert_ne!(palette.get(index), default.get(index), "{index}");
+        }
+        for index in [0, 1, 10, 52, 99] {
+            assert_eq!(palette.get(index), default.get(index), "{index}");
+        }
+        let mut result = Vec::new();
+        ircat_with_palette(
+            &mut BufReader::new(b"\x033green\x034red".as_ref()),
+            &mut result,
+            &palette,
+        )?;
+        assert_eq!(result, b"\x1b[38;5;36mgreen\x1b[38;5;166mred\x1b[39m");
+        Ok(())
+    }
+
+    #[test]
+    fn true_black() -> Result<()> {
+        let mut palette = Palette::default();
+        for (true_black, expected) in [
+            (true, b"\x1b[30m\x1b[47mart\x1b[39m\x1b[49m".as_ref()),
+            (false, b"\x1b[38;5;235m\x1b[47mart\x1b[39m\x1b[49m"),
+        ] {
+            palette.set_true_black(true_black);
+            let mut result = Vec::new();
+            ircat_with_palette(
+                &mut BufReader::new(b"\x031,0art".as_ref()),
+                &mut result,
+                &palette,
+            )?;
+            assert_eq!(result, expected, "true_black {true_black}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn reset_sequence() -> Result<()> {
+        let check = |builder: IrcatBuilder, formatting: &[u8], expected: &[u8]| -> Result<()> {
+            let output = builder.reset_sequence(formatting);
+            assert_eq!(output, expected, "{formatting:?}");
+
+            let mut input = formatting.to_vec();
+            input.extend_from_slice(b"a\nb");
+            let mut result = Vec::new();
+            builder.run(&mut BufReader::new(input.as_slice()), &mut result)?;
+            let line = result.split(|c| *c == b'\n').next().unwrap_or_default();
+            assert!(line.ends_with(&output), "{formatting:?}");
+            Ok(())
+        };
+
+        check(IrcatBuilder::default(), b"\x034,2", b"\x1b[39m\x1b[49m")?;
+        check(
+            IrcatBuilder::default().combine_resets(true),
+            b"\x034,2",
+            b"\x1b[39;49m",
+        )?;
+        check(IrcatBuilder::default(), b"\x034", b"\x1b[39m")?;
+        check(IrcatBuilder::default(), b"\x034\x02", b"\x1b[39m\x1b[22m")?;
+        check(IrcatBuilder::default(), b"", b"")?;
+        check(IrcatBuilder::default().default_fg(0), b"\x034", b"\x1b[37m")?;
+        check(
+            IrcatBuilder::default().dim(true),
+            b"\x034",
+            b"\x1b[39m\x1b[22m",
+        )
+    }
+
+    #[test]
+    fn true_black_separately() -> Result<()> {
+        for (fg, bg, expected) in [
+            (
+                false,
+                false,
+                b"\x1b[38;5;235m\x1b[48;5;235mart\x1b[39m\x1b[49m".as_ref(),
+            ),
+            (true, false, b"\x1b[30m\x1b[48;5;235mart\x1b[39m\x1b[49m"),
+            (false, true, b"\x1b[38;5;235m\x1b[40mart\x1b[39m\x1b[49m"),
+            (true, true, b"\x1b[30m\x1b[40mart\x1b[39m\x1b[49m"),
+        ] {
+            let mut result = Vec::new();
+            IrcatBuilder::default()
+                .true_black_fg(fg)
+                .true_black_bg(bg)
+                .run(&mut BufReader::new(b"\x031,1art".as_ref()), &mut result)?;
+            assert_eq!(result, expected, "fg {fg} bg {bg}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn color_mode() -> Result<()> {
+        for (color_mode, expected) in [
+            (
+                ColorMode::Ansi16,
+                b"\x1b[91m\x1b[44mred\x1b[33morange\x1b[92m\x1b[39m\x1b[49m".as_ref(),
+            ),
+            (
+                ColorMode::Ansi256,
+                b"\x1b[31m\x1b[44mred\x1b[38;5;209morange\x1b[38;5;46m\x1b[39m\x1b[49m",
+            ),
+            (
+                ColorMode::TrueColor,
+                b"\x1b[38;2;255;0;0m\x1b[48;2;0;0;127mred\x1b[38;2;252;127;0morange\
+                \x1b[38;2;0;255;0m\x1b[39m\x1b[49m",
+            ),
+        ] {
+            let mut result = Vec::new();
+            ircat_with_color_mode(
+                &mut BufReader::new(b"\x034,2red\x037orange\x0356".as_ref()),
+                &mut result,
+                color_mode,
+            )?;
+            assert_eq!(result, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn auto() -> Result<()> {
+        let input = b"\x034red\n";
+        for (no_color, expected) in [
+            (None, b"\x1b[31mred\x1b[39m\n".as_ref()),
+            (Some(""), b"\x1b[31mred\x1b[39m\n"),
+            (Some("1"), b"red\n"),
+        ] {
+            let mut result = Vec::new();
+            ircat_no_color(
+                &mut BufReader::new(input.as_ref()),
+                &mut result,
+                no_color.map(OsStr::new),
+            )?;
+            assert_eq!(result, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn stats() -> Result<()> {
+        let mut result = Vec::new();
+        let (written, stats) = ircat_with_stats(
+            &mut BufReader::new(b"\x034,2a\x033b\x04ff0000c\x03\x0f\x04f0d\x02e".as_ref()),
+            &mut result,
+        )?;
+        assert_eq!(written, u64::try_from(result.len()).unwrap_or_default());
+        assert_eq!(
+            stats,
+            Stats {
+                foreground: 3,
+                background: 1,
+                resets: 2,
+                unknown: 1,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn output_len() {
+        let mut long = b"\x02long ".to_vec();
+        long.extend_from_slice(&[b'a'; 1000]);
+        long.extend_from_slice(b"\x034,2\x16");
+        for input in [
+            b"".as_ref(),
+            b"plain\n",
+            b"\x034,12red\x02bold\x03,\x0f\x1dz",
+            b"\x04ff0000,00ff00hex\x0399\x1f\n\x1e\x11open",
+            b"\x03",
+            b"\x039,",
+            b"\x034\xc3\xa9\xff\x1b[1m\r\n",
+            long.as_slice(),
+        ] {
+            assert_eq!(
+                ansi_output_len(input),
+                ircat_bytes(input).len(),
+                "{}",
+                input.escape_ascii()
+            );
+        }
+    }
+
+    #[test]
+    fn histogram() {
+        let histogram = color_histogram(
+            b"\x034,12a\x0304b\x03,12c\x04ff0000,00ff00d\x0399,1e\x02\x039,9f\x034",
+        );
+        let mut expected = [0; 100];
+        expected[1] = 1;
+        expected[4] = 3;
+        expected[9] = 2;
+        expected[12] = 1;
+        expected[99] = 1;
+        assert_eq!(histogram, expected);
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(
+            ircat_bytes(b"\x034,2red\xff\x02\n"),
+            b"\x1b[31m\x1b[44mred\xff\x1b[1m\x1b[39m\x1b[49m\x1b[22m\n"
+        );
+    }
+
+    #[test]
+    fn read() -> Result<()> {
+        /// A reader that only implements `Read`.
+        struct Bytes<'a>(&'a [u8]);
+
+        impl Read for Bytes<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+                self.0.read(buf)
+            }
+        }
+
+        let input = b"\x034,2red\x02bold\n\x04ff8000orange";
+        let mut expected = Vec::new();
+        ircat(BufReader::new(input.as_ref()), &mut expected)?;
+
+        let mut result = Vec::new();
+        let written = ircat_read(Bytes(input), &mut result)?;
+        assert_eq!(result, expected);
+        assert_eq!(written, u64::try_from(result.len()).unwrap_or_default());
+        Ok(())
+    }
+
+    #[test]
+    fn line_buffered() -> Result<()> {
+        /// Record what had been written at each flush.
+        #[derive(Default)]
+        struct Flushes {
+            written: Vec<u8>,
+            flushed: Vec<Vec<u8>>,
+        }
+
+        impl Write for Flushes {
+            fn write(&mut self, buf: &[u8]) -> Result<usize> {
+                self.written.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> Result<()> {
+                self.flushed.push(self.written.clone());
+                Ok(())
+            }
+        }
+
+        let input = b"\x034red\nplain\n\x02bold";
+        for capacity in [1, 4, 64] {
+            let mut writer = Flushes::default();
+            let written = ircat_line_buffered(
+                BufReader::with_capacity(capacity, input.as_ref()),
+                &mut writer,
+            )?;
+            assert_eq!(
+                writer.flushed,
+                [
+                    b"\x1b[31mred\x1b[39m\n".as_ref(),
+                    b"\x1b[31mred\x1b[39m\nplain\n",
+                    b"\x1b[31mred\x1b[39m\nplain\n\x1b[1mbold\x1b[22m",
+                ],
+                "capacity {capacity}"
+            );
+            assert_eq!(
+                written,
+                u64::try_from(writer.written.len()).unwrap_or_default()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn lines() -> Result<()> {
+        let input = b"\x034,2red\x02bold\n\n\x1funderline\r\nplain";
+        let lines = ircat_lines(BufReader::new(input.as_ref())).collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            lines,
+            [
+                "\x1b[31m\x1b[44mred\x1b[1mbold\x1b[39m\x1b[49m\x1b[22m",
+                "",
+                "\x1b[4munderline\x1b[24m",
+                "plain",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lines_invalid_utf8() {
+        let mut lines = ircat_lines(BufReader::new(b"ok\n\xff\n".as_ref()));
+        assert_eq!(lines.next().map(Result::ok), Some(Some("ok".to_owned())));
+        assert_eq!(
+            lines.next().and_then(Result::err).map(|e| e.kind()),
+            Some(ErrorKind::InvalidData)
+        );
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn detect() {
+        for (colorterm, term, expected) in [
+            ("truecolor", "", ColorMode::TrueColor),
+            ("24bit", "xterm", ColorMode::TrueColor),
+            ("", "xterm-256color", ColorMode::Ansi256),
+            ("", "screen-256color", ColorMode::Ansi256),
+            ("", "xterm", ColorMode::Ansi16),
+            ("", "", ColorMode::Ansi16),
+        ] {
+            assert_eq!(ColorMode::detect_from(colorterm, term), expected);
+        }
+    }
+
+    #[test]
+    fn bright() -> Result<()> {
+        for (color_mode, expected) in [
+            (
+                ColorMode::Ansi16,
+                b"\x1b[34m\x1b[102mblue\x1b[92mgreen\x1b[97m\x1b[39m\x1b[49m".as_ref(),
+            ),
+            (
+                ColorMode::Ansi256,
+                b"\x1b[34m\x1b[102mblue\x1b[92mgreen\x1b[97m\x1b[39m\x1b[49m",
+            ),
+            (
+                ColorMode::TrueColor,
+                b"\x1b[38;2;0;0;127m\x1b[48;2;0;252;0mblue\x1b[38;2;0;252;0mgreen\
+                \x1b[38;2;210;210;210m\x1b[39m\x1b[49m",
+            ),
+        ] {
+            let mut result = Vec::new();
+            IrcatBuilder::default()
+                .color_mode(color_mode)
+                .bright(true)
+                .run(
+                    &mut BufReader::new(b"\x032,9blue\x039green\x0315".as_ref()),
+                    &mut result,
+                )?;
+            assert_eq!(result, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn disable_attributes() -> Result<()> {
+        for (output, input, expected) in [
+            (OutputFormat::Ansi, b"\x1dfoo\x1d".as_ref(), b"foo".as_ref()),
+            (
+                OutputFormat::Ansi,
+                b"\x02\x1d\x034bold red\x1d\x0f \x1funderlined\x1f \x1er",
+                b"\x1b[1m\x1b[31mbold red\x1b[0m underlined \x1b[9mr\x1b[29m",
+            ),
+            (
+                OutputFormat::Html,
+                b"\x1d\x02foo",
+                b"<span style=\"font-weight:bold\">foo</span>",
+            ),
+        ] {
+            let mut result = Vec::new();
+            IrcatBuilder::default()
+                .enable(Attribute::Italic, false)
+                .enable(Attribute::Underline, false)
+                .enable(Attribute::Bold, false)
+                .enable(Attribute::Bold, true)
+                .output(output)
+                .run(&mut BufReader::new(input), &mut result)?;
+            assert_eq!(result, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn strict() -> result::Result<(), IrcatError> {
+        let input = b"\x034,2red\x0f \x04ff0000hex\x03\n";
+        for capacity in [1, 3, 8192] {
+            let mut result = Vec::new();
+            ircat_strict(
+                BufReader::with_capacity(capacity, input.as_ref()),
+                &mut result,
+            )?;
+            assert_eq!(result, ircat_bytes(input), "capacity {capacity}");
+        }
+
+        for (input, error) in [
+            (
+                b"ok\nok \x04ff0 red".as_ref(),
+                FormatError::IncompleteHexColor { offset: 6 },
+            ),
+            (b"ok\x03", FormatError::Dangling { offset: 2 }),
+            (
+                b"ok\nbell\x07",
+                FormatError::UnknownControl {
+                    offset: 7,
+                    byte: 0x07,
+                },
+            ),
+        ] {
+            for capacity in [1, 3, 8192] {
+                let mut result = Vec::new();
+                let reader = BufReader::with_capacity(capacity, input);
+                match ircat_strict(reader, &mut result) {
+                    Err(IrcatError::Format(e)) => assert_eq!(e, error, "capacity {capacity}"),
+                    result => panic!("unexpected {result:?} at capacity {capacity}"),
+                }
+                // Output stops before the chunk holding the error.
+                assert!(ircat_bytes(input).starts_with(&result));
+            }
+            // Lenient translation carries on.
+            let mut result = Vec::new();
+            ircat(BufReader::new(input), &mut result)?;
+            assert_eq!(result, ircat_bytes(input));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn strict_io_error() {
+        struct Fail;
+
+        impl Write for Fail {
+            fn write(&mut self, _buf: &[u8]) -> Result<usize> {
+                Err(ErrorKind::PermissionDenied.into())
+            }
+
+            fn flush(&mut self) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        let result = ircat_strict(BufReader::new(b"text".as_ref()), &mut Fail);
+        assert!(
+            matches!(result, Err(IrcatError::Io(e)) if e.kind() == ErrorKind::PermissionDenied)
+        );
+    }
+
+    #[test]
+    fn interrupted() -> Result<()> {
+        struct Interrupt<'a> {
+            interrupted: bool,
+            inner: &'a [u8],
+        }
+
+        impl Read for Interrupt<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+                let len = self.fill_buf()?.read(buf)?;
+                self.consume(len);
+                Ok(len)
+            }
+        }
+
+        impl BufRead for Interrupt<'_> {
+            fn fill_buf(&mut self) -> Result<&[u8]> {
+                if !self.interrupted {
+                    self.interrupted = true;
+                    return Err(ErrorKind::Interrupted.into());
+                }
+                Ok(self.inner)
+            }
+
+            fn consume(&mut self, amt: usize) {
+                self.inner = &self.inner[amt..];
+            }
+        }
+
+        let mut reader = BufFilter::<IrcatFilter, _>::new(Interrupt {
+            interrupted: false,
+            inner: b"\x034red",
+        });
+        assert_eq!(reader.fill_buf()?, b"\x1b[31mred");
+        reader.consume(8);
+        assert_eq!(reader.fill_buf()?, b"\x1b[39m");
+        Ok(())
+    }
+
+    #[test]
+    fn reset_byte() -> Result<()> {
+        for (byte, input, expected) in [
+            (
+                b'\x00',
+                b"\x034,2\x02red\x00plain\x0f".as_ref(),
+                b"\x1b[31m\x1b[44m\x1b[1mred\x1b[0mplain".as_ref(),
+            ),
+            (
+                b'~',
+                b"\x02bold~plain and more plain text~\x034~~",
+                b"\x1b[1mbold\x1b[0mplain and more plain text\x1b[31m\x1b[0m",
+            ),
+            (b'5', b"\x035red5", b"\x1b[38;5;52mred\x1b[0m"),
+            (b'\x02', b"\x02bold\x02", b"\x1b[1mbold\x1b[22m"),
+        ] {
+            let builder = IrcatBuilder::default().reset_byte(byte);
+            let mut result = Vec::new();
+            builder.run(&mut BufReader::new(input), &mut result)?;
+            assert_eq!(result, expected, "{byte:#x}");
+
+            let mut result = Vec::new();
+            builder.run(&mut BufReader::with_capacity(1, input), &mut result)?;
+            assert_eq!(result, expected, "{byte:#x} one byte at a time");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn color_byte() -> Result<()> {
+        let input = b"\x034,12red\x03 \x0302,3blue\x03,5 plain words and more \x039,x\x0312\n\x03";
+        let mut expected = Vec::new();
+        ircat(&mut BufReader::new(input.as_ref()), &mut expected)?;
+        for byte in [b'\x1a', b'^'] {
+            let remapped: Vec<_> = input
+                .iter()
+                .map(|c| if *c == b'\x03' { byte } else { *c })
+                .collect();
+            let builder = IrcatBuilder::default().color_byte(byte);
+            let mut result = Vec::new();
+            builder.run(&mut BufReader::new(remapped.as_slice()), &mut result)?;
+            assert_eq!(result, expected, "{byte:#x}");
+
+            let mut result = Vec::new();
+            builder.run(
+                &mut BufReader::with_capacity(1, remapped.as_slice()),
+                &mut result,
+            )?;
+            assert_eq!(result, expected, "{byte:#x} one byte at a time");
+
+            let mut result = Vec::new();
+            builder.run(&mut BufReader::new(input.as_ref()), &mut result)?;
+            assert_eq!(result, expected, "{byte:#x} with \\x03");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn blink_byte() -> Result<()> {
+        let input = b"\x06blink\x06 \x02\x06both\n\x06x\x0f";
+        for (builder, expected) in [
+            (
+                IrcatBuilder::default().blink_byte(b'\x06'),
+                b"\x1b[5mblink\x1b[25m \x1b[1m\x1b[5mboth\x1b[22m\x1b[25m\n\x1b[5mx\x1b[0m"
+                    .as_ref(),
+            ),
+            (
+                IrcatBuilder::default(),
+                b"\x06blink\x06 \x1b[1m\x06both\x1b[22m\n\x06x",
+            ),
+            (
+                IrcatBuilder::default()
+                    .blink_byte(b'\x06')
+                    .output(OutputFormat::Html),
+                b"blink <span style=\"font-weight:bold\">both</span>\nx",
+            ),
+        ] {
+            let mut result = Vec::new();
+            builder.run(&mut BufReader::new(input.as_ref()), &mut result)?;
+            assert_eq!(result, expected, "{builder:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bell() -> Result<()> {
+        let input = b"\x07ding\x034\x07\x07 \x02\x07\x02end\x07";
+        for (bell, expected) in [
+            (
+                Bell::Keep,
+                b"\x07ding\x1b[31m\x07\x07 \x1b[1m\x07\x1b[22mend\x07\x1b[39m".as_ref(),
+            ),
+            (Bell::Drop, b"ding\x1b[31m \x1b[1m\x1b[22mend\x1b[39m"),
+            (
+                Bell::Show,
+                "\u{2407}ding\x1b[31m\u{2407}\u{2407} \x1b[1m\u{2407}\x1b[22mend\u{2407}\x1b[39m"
+                    .as_bytes(),
+            ),
+        ] {
+            let builder = IrcatBuilder::default().bell(bell);
+            let mut result = Vec::new();
+            builder.run(&mut BufReader::new(input.as_ref()), &mut result)?;
+            assert_eq!(result, expected, "{bell:?}");
+
+            let mut result = Vec::new();
+            builder.run(
+                &mut BufReader::with_capacity(1, input.as_ref()),
+                &mut result,
+            )?;
+            assert_eq!(result, expected, "{bell:?} one byte at a time");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cow() {
+        for input in [b"".as_ref(), b"plain", b"two\r\nlines\n", b"\x1b[1m\x07\t"] {
+            assert!(matches!(ircat_cow(input), Cow::Borrowed(borrowed) if borrowed == input));
+        }
+        for input in [
+            b"\x034red".as_ref(),
+            b"\x03",
+            b"\x02\x02",
+            b"\x0f",
+            b"text\x1d",
+            b"\x04ff0000,00ff00",
+        ] {
+            let result = ircat_cow(input);
+            assert!(matches!(result, Cow::Owned(_)));
+            assert_eq!(*result, ircat_bytes(input));
+        }
+    }
+
+    #[test]
+    fn uniform_colors() -> Result<()> {
+        let input = b"\x034,2red\x0313pink\x0312,9blue\x04ff0000hex\x03";
+        for (color_mode, bright, compact, uniform) in [
+            (
+                ColorMode::Ansi256,
+                false,
+                b"\x1b[31m\x1b[44mred\x1b[38;5;200mpink\x1b[38;5;56m\x1b[48;5;47mblue\
+                \x1b[38;2;255;0;0mhex\x1b[39m\x1b[49m"
+                    .as_ref(),
+                b"\x1b[38;5;1m\x1b[48;5;4mred\x1b[38;5;200mpink\x1b[38;5;56m\x1b[48;5;47mblue\
+                \x1b[38;2;255;0;0mhex\x1b[39m\x1b[49m"
+                    .as_ref(),
+            ),
+            (
+                ColorMode::Ansi16,
+                false,
+                b"\x1b[91m\x1b[44mred\x1b[95mpink\x1b[34m\x1b[102mblue\
+                \x1b[38;2;255;0;0mhex\x1b[39m\x1b[49m",
+                b"\x1b[38;5;9m\x1b[48;5;4mred\x1b[38;5;13mpink\x1b[38;5;4m\x1b[48;5;10mblue\
+                \x1b[38;2;255;0;0mhex\x1b[39m\x1b[49m",
+            ),
+            (
+                ColorMode::Ansi256,
+                true,
+                b"\x1b[31m\x1b[44mred\x1b[95mpink\x1b[94m\x1b[102mblue\
+                \x1b[38;2;255;0;0mhex\x1b[39m\x1b[49m",
+                b"\x1b[38;5;1m\x1b[48;5;4mred\x1b[38;5;13mpink\x1b[38;5;12m\x1b[48;5;10mblue\
+                \x1b[38;2;255;0;0mhex\x1b[39m\x1b[49m",
+            ),
+        ] {
+            for (uniform_colors, expected) in [(false, compact), (true, uniform)] {
+                let mut result = Vec::new();
+                IrcatBuilder::default()
+                    .color_mode(color_mode)
+                    .bright(bright)
+                    .uniform_colors(uniform_colors)
+                    .run(&mut BufReader::new(input.as_ref()), &mut result)?;
+                assert_eq!(result, expected, "{color_mode:?} {bright} {uniform_colors}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn quantize_hex() -> Result<()> {
+        for (color_mode, expected) in [
+            (
+                ColorMode::Ansi16,
+                b"\x1b[91ma\x1b[32m\x1b[44mb\x1b[90mc\x1b[97md\x1b[39m\x1b[49m".as_ref(),
+            ),
+            (
+                ColorMode::Ansi256,
+                b"\x1b[38;5;196ma\x1b[38;5;28m\x1b[48;5;18mb\x1b[38;5;244mc\x1b[38;5;231md\
+                \x1b[39m\x1b[49m",
+            ),
+            (
+                ColorMode::TrueColor,
+                b"\x1b[38;2;255;0;0ma\x1b[38;2;0;128;0m\x1b[48;2;0;0;128mb\x1b[38;2;128;128;128mc\
+                \x1b[38;2;255;255;255md\x1b[39m\x1b[49m",
+            ),
+        ] {
+            let mut result = Vec::new();
+            IrcatBuilder::default()
+                .color_mode(color_mode)
+                .quantize_hex(true)
+                .run(
+                    &mut BufReader::new(
+                        b"\x04ff0000a\x04008000,000080b\x04808080c\x04ffffffd".as_ref(),
+                    ),
+                    &mut result,
+                )?;
+            assert_eq!(result, expected, "{color_mode:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn prompt_escapes() -> Result<()> {
+        for (open, close, input, expected) in [
+            (
+                "\\[",
+                "\\]",
+                b"\x034,2red\x03 plain\n".as_ref(),
+                b"\\[\x1b[31m\x1b[44m\\]red\\[\x1b[39m\x1b[49m\\] plain\n".as_ref(),
+            ),
+            (
+                "\\[",
+                "\\]",
+                b"\x02bold \x034red",
+                b"\\[\x1b[1m\\]bold \\[\x1b[31m\\]red\\[\x1b[39m\x1b[22m\\]",
+            ),
+            ("%{", "%}", b"\x1fa\nb", b"%{\x1b[4m%}a%{\x1b[24m%}\nb"),
+        ] {
+            let mut result = Vec::new();
+            IrcatBuilder::default()
+                .prompt_escapes(open, close)
+                .run(&mut BufReader::new(input), &mut result)?;
+            assert_eq!(result, expected, "{}", input.escape_ascii());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bold_bright() -> Result<()> {
+        for (input, off, on) in [
+            (
+                b"\x02\x034bold-red".as_ref(),
+                b"\x1b[1m\x1b[31mbold-red\x1b[39m\x1b[22m".as_ref(),
+                b"\x1b[1m\x1b[91mbold-red\x1b[39m\x1b[22m".as_ref(),
+            ),
+            (
+                b"\x034red\x02bold\x02red",
+                b"\x1b[31mred\x1b[1mbold\x1b[22mred\x1b[39m",
+                b"\x1b[31mred\x1b[1m\x1b[91mbold\x1b[22m\x1b[31mred\x1b[39m",
+            ),
+            (
+                b"\x02\x0312,4a\x02b",
+                b"\x1b[1m\x1b[38;5;56m\x1b[41ma\x1b[22mb\x1b[39m\x1b[49m",
+                b"\x1b[1m\x1b[38;5;56m\x1b[41ma\x1b[22mb\x1b[39m\x1b[49m",
+            ),
+        ] {
+            for (bold_bright, expected) in [(false, off), (true, on)] {
+                let mut result = Vec::new();
+                IrcatBuilder::default()
+                    .bold_bright(bold_bright)
+                    .run(&mut BufReader::new(input), &mut result)?;
+                assert_eq!(result, expected, "{}", input.escape_ascii());
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that each `NAME.txt` in `tests/fixtures` translates to `NAME.ansi`.
+    #[test]
+    fn fixtures() -> Result<()> {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let mut failures = String::new();
+        let mut count = 0;
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "txt") {
+                continue;
+            }
+            let expected = fs::read(path.with_extension("ansi"))?;
+            let mut result = Vec::new();
+            ircat(BufReader::new(fs::File::open(&path)?), &mut result)?;
+            count += 1;
+            if result != expected {
+                let _ = writeln!(failures, "{}:", path.display());
+                diff(&expected, &result, &mut failures);
+            }
+        }
+        assert_ne!(count, 0, "no fixtures found");
+        assert!(failures.is_empty(), "fixtures differ\n{failures}");
+        Ok(())
+    }
+
+    /// Describe the lines that differ between `expected` and `result` with their bytes escaped.
+    fn diff(expected: &[u8], result: &[u8], output: &mut String) {
+        let mut expected_lines = expected.split_inclusive(|c| *c == b'\n');
+        let mut result_lines = result.split_inclusive(|c| *c == b'\n');
+        for line in 1.. {
+            match (expected_lines.next(), result_lines.next()) {
+                (None, None) => break,
+                (expected, result) if expected == result => {}
+                (expected, result) => {
+                    let _ = writeln!(output, "  line {line}:");
+                    if let Some(expected) = expected {
+                        let _ = writeln!(output, "  - {}", expected.escape_ascii());
+                    }
+                    if let Some(result) = result {
+                        let _ = writeln!(output, "  + {}", result.escape_ascii());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_limit() -> Result<()> {
+        let input = b"\x034,2red\x02bold\x0f plain\n".repeat(10);
+        let mut expected = Vec::new();
+        ircat(&mut BufReader::new(input.as_slice()), &mut expected)?;
+        for limit in [0, 1, 2, 7, 64] {
+            let mut filter = BufFilter::<IrcatFilter, _>::new(BufReader::new(input.as_slice()))
+                .with_chunk_limit(limit);
+            let mut result = Vec::new();
+            loop {
+                let buffer = filter.fill_buf()?;
+                if buffer.is_empty() {
+                    break;
+                }
+                assert!(buffer.len() <= 32 * cmp::max(limit, 1));
+                result.extend_from_slice(buffer);
+                let len = buffer.len();
+                filter.consume(len);
+            }
+            assert_eq!(result, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_limit_long_line() -> Result<()> {
+        // Several megabytes on one line arriving in a single read, with a color split across the
+        // first chunk boundary.
+        let limit = 4096;
+        let mut input = b"x".repeat(limit - 1);
+        input.extend_from_slice(b"\x0312,4");
+        input.extend(b"plain text ".repeat(300_000));
+        input.extend_from_slice(b"\x03\n");
+        let mut expected = b"x".repeat(limit - 1);
+        expected.extend_from_slice(b"\x1b[38;5;56m\x1b[41m");
+        expected.extend(b"plain text ".repeat(300_000));
+        expected.extend_from_slice(b"\x1b[39m\x1b[49m\n");
+
+        let mut filter = BufFilter::<IrcatFilter, _>::new(input.as_slice()).with_chunk_limit(limit);
+        let mut result = Vec::new();
+        loop {
+            let buffer = filter.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+            assert!(buffer.len() <= 2 * limit);
+            result.extend_from_slice(buffer);
+            let len = buffer.len();
+            filter.consume(len);
+        }
+        assert_eq!(result, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn reset_lines() -> Result<()> {
+        let mut result = Vec::new();
+        IrcatBuilder::default().reset_lines(true).run(
+            &mut BufReader::new(b"\x034red\r\nplain\n\n\x02bold\n".as_ref()),
+            &mut result,
+        )?;
+        assert_eq!(
+            result,
+            b"\x1b[0m\x1b[31mred\x1b[39m\r\n\x1b[0mplain\n\n\x1b[0m\x1b[1mbold\x1b[22m\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dim() -> Result<()> {
+        for (reset_lines, input, expected) in [
+            (
+                false,
+                b"plain\n\nnext".as_ref(),
+                b"\x1b[2mplain\x1b[22m\n\n\x1b[2mnext\x1b[22m".as_ref(),
+            ),
+            (
+                false,
+                b"\x034,2red\x03plain\n",
+                b"\x1b[2m\x1b[31m\x1b[44mred\x1b[39m\x1b[49mplain\x1b[22m\n",
+            ),
+            (
+                false,
+                b"\x02bold\x02\x034red\x0fplain\n",
+                b"\x1b[2m\x1b[1mbold\x1b[22m\x1b[2m\x1b[31mred\x1b[0m\x1b[2mplain\x1b[22m\n",
+            ),
+            (
+                false,
+                b"\x02\x034bold",
+                b"\x1b[2m\x1b[1m\x1b[31mbold\x1b[39m\x1b[22m",
+            ),
+            (
+                true,
+                b"\x034red\n",
+                b"\x1b[0m\x1b[2m\x1b[31mred\x1b[39m\x1b[22m\n",
+            ),
+        ] {
+            let mut result = Vec::new();
+            IrcatBuilder::default()
+                .dim(true)
+                .reset_lines(reset_lines)
+                .run(&mut BufReader::new(input), &mut result)?;
+            assert_eq!(result, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn combine_resets() -> Result<()> {
+        let input = b"\x034,2a\x03b\x035c\n\x034,2d";
+        for (combine_resets, expected) in [
+            (
+                false,
+                b"\x1b[31m\x1b[44ma\x1b[39m\x1b[49mb\x1b[38;5;52mc\x1b[39m\n\
+                \x1b[31m\x1b[44md\x1b[39m\x1b[49m"
+                    .as_ref(),
+            ),
+            (
+                true,
+                b"\x1b[31m\x1b[44ma\x1b[39;49mb\x1b[38;5;52mc\x1b[39m\n\
+                \x1b[31m\x1b[44md\x1b[39;49m",
+            ),
+        ] {
+            let mut result = Vec::new();
+            IrcatBuilder::default()
+                .combine_resets(combine_resets)
+                .run(&mut BufReader::new(input.as_ref()), &mut result)?;
+            assert_eq!(result, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn translate_message() {
+        let mut filter = IrcatFilter::init();
+        for (message, expected) in [
+            (b"plain".as_ref(), (b"plain".as_ref(), false)),
+            (b"\x034", (b"\x1b[31m", true)),
+            (b"still red\x03", (b"still red\x1b[39m", false)),
+            (b"\x1f\x034,2", (b"\x1b[4m\x1b[31m\x1b[44m", true)),
+            (b"\x0f", (b"\x1b[0m", false)),
+            (b"\x02bold\n", (b"\x1b[1mbold\x1b[22m\n", false)),
+        ] {
+            let (output, open) = filter.translate_message(message);
+            assert_eq!((output.as_slice(), open), expected);
+        }
+    }
+
+    #[test]
+    fn filter_reset() -> Result<()> {
+        let mut writer = FilterWriter::<IrcatFilter, _>::new(Vec::new());
+        writer.write_all(b"\x034,2red\x02bold\x03")?;
+        writer.get_filter_mut().reset();
+        writer.write_all(b"3plain\x02bold")?;
+        writer.flush()?;
+        assert_eq!(
+            writer.into_inner(),
+            b"\x1b[31m\x1b[44mred\x1b[1mbold3plain\x1b[1mbold\x1b[22m"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ansi_passthrough() -> Result<()> {
+        for (output, input, expected) in [
+            (
+                OutputFormat::Ansi,
+                b"\x1b[1mbold\x032blue\x1b[0m".as_ref(),
+                b"\x1b[1mbold\x1b[34mblue\x1b[0m\x1b[39m".as_ref(),
+            ),
+            (
+                OutputFormat::Ansi,
+                b"\x1b[38;5;2\x032blue\x1b[",
+                b"\x1b[34mblue\x1b[39m",
+            ),
+            (OutputFormat::Ansi, b"\x1bc\x1b", b"\x1bc\x1b"),
+            (
+                OutputFormat::Strip,
+                b"\x1b[1mbold\x032blue\x1b[0m",
+                b"boldblue",
+            ),
+        ] {
+            for capacity in [1, 64] {
+                let mut result = Vec::new();
+                IrcatBuilder::default()
+                    .output(output)
+                    .ansi_passthrough(true)
+                    .run(&mut BufReader::with_capacity(capacity, input), &mut result)?;
+                assert_eq!(result, expected);
+            }
+        }
+
+        let mut result = Vec::new();
+        ircat(
+            &mut BufReader::new(b"\x1b[38;5;2\x032blue".as_ref()),
+            &mut result,
+        )?;
+        assert_eq!(result, b"\x1b[38;5;2\x1b[34mblue\x1b[39m");
+        Ok(())
+    }
+
+    #[test]
+    fn long_ansi_sequence() -> Result<()> {
+        let mut input = b"\x1b[".to_vec();
+        for _ in 0..70 {
+            input.extend_from_slice(b"1;");
+        }
+        input.extend_from_slice(b"mX\x1b[1mbold");
+        for capacity in [1, 64] {
+            let mut result = Vec::new();
+            IrcatBuilder::default().ansi_passthrough(true).run(
+                &mut BufReader::with_capacity(capacity, input.as_slice()),
+                &mut result,
+            )?;
+            assert_eq!(result, b"X\x1b[1mbold");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn default_colors() -> Result<()> {
+        let input = b"\x034,2a\x03b\x0399c\x034d\x0fe\n";
+        for (builder, expected) in [
+            (
+                IrcatBuilder::default().default_fg(0).default_bg(1),
+                b"\x1b[31m\x1b[44ma\x1b[37m\x1b[48;5;235mbc\x1b[31md\x1b[0m\x1b[37m\x1b[48;5;235me\n"
+                    .as_ref(),
+            ),
+            (
+                IrcatBuilder::default().default_fg(0),
+                b"\x1b[31m\x1b[44ma\x1b[37m\x1b[49mbc\x1b[31md\x1b[0m\x1b[37me\n",
+            ),
+            (
+                IrcatBuilder::default()
+                    .default_bg(1)
+                    .combine_resets(true),
+                b"\x1b[31m\x1b[44ma\x1b[39m\x1b[48;5;235mbc\x1b[31md\x1b[0m\x1b[48;5;235me\n",
+            ),
+        ] {
+            let mut result = Vec::new();
+            builder.run(&mut BufReader::new(input.as_ref()), &mut result)?;
+            assert_eq!(result, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn writer() -> Result<()> {
+        let mut writer = FilterWriter::<IrcatFilter, _>::new(Vec::new());
+        writer.write_all(b"\x033")?;
+        writer.write_all(b",2green\x02")?;
+        writer.flush()?;
+        let color = 4;
+        write!(writer, "\x03{color}red")?;
+        writer.flush()?;
+        assert_eq!(
+            writer.into_inner(),
+            b"\x1b[32m\x1b[44mgreen\x1b[1m\x1b[39m\x1b[49m\x1b[22m\x1b[31mred\x1b[39m"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn str() {
+        assert_eq!(
+            ircat_str("\x034r\u{e9}d\x02\u{1f600}\n"),
+            "\x1b[31mr\u{e9}d\x1b[1m\u{1f600}\x1b[39m\x1b[22m\n"
+        );
+    }
+
+    #[test]
+    fn builder() -> Result<()> {
+        let mut palette = Palette::default();
+        palette.set(4, "8;5;196");
+        for (builder, expected) in [
+            (
+                IrcatBuilder::default(),
+                b"\x1b[31m\x1b[1mred\x1b[39m\x1b[22m".as_ref(),
+            ),
+            (
+                IrcatBuilder::default().palette(palette),
+                b"\x1b[38;5;196m\x1b[1mred\x1b[39m\x1b[22m",
+            ),
+            (
+                IrcatBuilder::default().color_mode(ColorMode::Ansi16),
+                b"\x1b[91m\x1b[1mred\x1b[39m\x1b[22m",
+            ),
+            (IrcatBuilder::default().output(OutputFormat::Strip), b"red"),
+            (
+                IrcatBuilder::default().output(OutputFormat::Html),
+                b"<span style=\"color:#ff0000;font-weight:bold\">red</span>",
+            ),
+        ] {
+            let mut result = Vec::new();
+            builder.run(&mut BufReader::new(b"\x034\x02red".as_ref()), &mut result)?;
+            assert_eq!(result, expected);
+        }
+        Ok(())
     }
 }
@@ -1,6 +1,66 @@
+use std::env;
+use std::fs::File;
 use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::ErrorKind;
+use std::process::ExitCode;
 
-fn main() -> io::Result<()> {
-    ircat::ircat(io::stdin().lock(), &mut io::stdout())?;
-    Ok(())
+const USAGE: &str = "usage: ircat [--strip] [FILE]...";
+
+fn main() -> ExitCode {
+    let mut strip = false;
+    let mut paths = Vec::new();
+    for arg in env::args_os().skip(1) {
+        if arg == "--strip" {
+            strip = true;
+        } else if arg == "-h" || arg == "--help" {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        } else {
+            paths.push(arg);
+        }
+    }
+    if paths.is_empty() {
+        paths.push("-".into());
+    }
+
+    if !strip {
+        // Without it a Windows console prints the escape sequences raw. Output that does not go to
+        // a console is fine either way.
+        let _ = ircat::enable_ansi_console();
+    }
+
+    let mut writer = io::stdout().lock();
+    for path in paths {
+        let reader: Box<dyn BufRead> = if path == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            match File::open(&path) {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(e) => {
+                    eprintln!("ircat: {}: {e}", path.to_string_lossy());
+                    return ExitCode::FAILURE;
+                }
+            }
+        };
+
+        // Each file gets a fresh filter so formatting left open by one does not leak into the
+        // next.
+        let result = if strip {
+            ircat::strip(reader, &mut writer)
+        } else {
+            ircat::ircat_auto(reader, &mut writer)
+        };
+        match result {
+            Ok(_) => {}
+            // The reader of our output went away so there is no one left to tell.
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => return ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("ircat: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,248 @@
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::color::lookup_rgb;
+use crate::color::xterm_rgb;
+use crate::parser::Attribute;
+use crate::parser::Event;
+use crate::write::VecWriter;
+
+/// Pango tags for the attributes in the order they are opened.
+const TAGS: [(Attribute, &str); 4] = [
+    (Attribute::Bold, "b"),
+    (Attribute::Italic, "i"),
+    (Attribute::Underline, "u"),
+    (Attribute::Strikethrough, "s"),
+];
+
+/// Renders parsed IRC formatting as Pango markup.
+#[derive(Debug)]
+pub(crate) struct Pango {
+    fg_color: Option<u32>,
+    bg_color: Option<u32>,
+    attributes: u8,
+    monospace: bool,
+    /// Whether tags for the current formatting are open in the output.
+    open: bool,
+}
+
+impl Pango {
+    pub(crate) fn new() -> Self {
+        Self {
+            fg_color: None,
+            bg_color: None,
+            attributes: 0,
+            monospace: false,
+            open: false,
+        }
+    }
+
+    pub(crate) fn render(&mut self, event: Event<'_>, output: &mut Vec<u8>) {
+        match event {
+            Event::Text(text) => {
+                self.open(output);
+                escape(text, output);
+            }
+            Event::LineEnd(c) => {
+                self.reset(output);
+                output.push(c);
+            }
+            // Pango has no markup for blink.
+            Event::Toggle(Attribute::Blink) => {}
+            Event::Toggle(attribute) => {
+                self.close(output);
+                self.attributes ^= attribute.mask();
+            }
+            Event::Monospace => {
+                self.close(output);
+                self.monospace = !self.monospace;
+            }
+            Event::Color(fg_color, bg_color) => {
+                self.close(output);
+                self.fg_color = lookup_rgb(fg_color);
+                if let Some(bg_color) = bg_color {
+                    self.bg_color = lookup_rgb(bg_color);
+                }
+            }
+            Event::HexColor(fg_color, bg_color) => {
+                self.close(output);
+                self.fg_color = Some(fg_color);
+                if let Some(bg_color) = bg_color {
+                    self.bg_color = Some(bg_color);
+                }
+            }
+            Event::Background(bg_color) => {
+                self.close(output);
+                self.bg_color = lookup_rgb(bg_color);
+            }
+            Event::HexBackground(bg_color) => {
+                self.close(output);
+                self.bg_color = Some(bg_color);
+            }
+            Event::XtermColor(fg_color, bg_color) => {
+                self.close(output);
+                self.fg_color = Some(xterm_rgb(fg_color));
+                if let Some(bg_color) = bg_color {
+                    self.bg_color = Some(xterm_rgb(bg_color));
+                }
+            }
+            Event::XtermBackground(bg_color) => {
+                self.close(output);
+                self.bg_color = Some(xterm_rgb(bg_color));
+            }
+            Event::ResetColor => {
+                self.close(output);
+                self.fg_color = None;
+                self.bg_color = None;
+            }
+            Event::Reset => self.reset(output),
+            // ANSI sequences have no meaning in Pango markup so drop them.
+            Event::Unknown | Event::Ansi(_) => {}
+        }
+    }
+
+    /// Whether any formatting is active.
+    pub(crate) fn is_active(&self) -> bool {
+        self.fg_color.is_some() || self.bg_color.is_some() || self.attributes != 0 || self.monospace
+    }
+
+    pub(crate) fn finish(&mut self, output: &mut Vec<u8>) {
+        self.reset(output);
+    }
+
+    fn reset(&mut self, output: &mut Vec<u8>) {
+        self.close(output);
+        self.fg_color = None;
+        self.bg_color = None;
+        self.attributes = 0;
+        self.monospace = false;
+    }
+
+    fn has_span(&self) -> bool {
+        self.fg_color.is_some() || self.bg_color.is_some()
+    }
+
+    /// Open tags for the current formatting.
+    fn open(&mut self, output: &mut Vec<u8>) {
+        if self.open {
+            return;
+        }
+
+        if self.has_span() {
+            output.extend_from_slice(b"<span");
+            // Writing to a Vec can't fail.
+            if let Some(fg_color) = self.fg_color {
+                let _ = write!(VecWriter(output), " foreground=\"#{fg_color:06x}\"");
+            }
+            if let Some(bg_color) = self.bg_color {
+                let _ = write!(VecWriter(output), " background=\"#{bg_color:06x}\"");
+            }
+            output.push(b'>');
+        }
+        // Reverse has no Pango equivalent so it is ignored.
+        for (attribute, tag) in TAGS {
+            if self.attributes & attribute.mask() != 0 {
+                let _ = write!(VecWriter(output), "<{tag}>");
+            }
+        }
+        if self.monospace {
+            output.extend_from_slice(b"<tt>");
+        }
+        self.open = true;
+    }
+
+    /// Close the tags opened for the current formatting in reverse order.
+    fn close(&mut self, output: &mut Vec<u8>) {
+        if !self.open {
+            return;
+        }
+
+        if self.monospace {
+            output.extend_from_slice(b"</tt>");
+        }
+        for (attribute, tag) in TAGS.into_iter().rev() {
+            if self.attributes & attribute.mask() != 0 {
+                // Writing to a Vec can't fail.
+                let _ = write!(VecWriter(output), "</{tag}>");
+            }
+        }
+        if self.has_span() {
+            output.extend_from_slice(b"</span>");
+        }
+        self.open = false;
+    }
+}
+
+fn escape(text: &[u8], output: &mut Vec<u8>) {
+    for c in text {
+        match c {
+            b'&' => output.extend_from_slice(b"&amp;"),
+            b'<' => output.extend_from_slice(b"&lt;"),
+            b'>' => output.extend_from_slice(b"&gt;"),
+            b'"' => output.extend_from_slice(b"&quot;"),
+            _ => output.push(*c),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+
+    use crate::ircat_pango;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let mut result = Vec::new();
+                    ircat_pango(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(String::from_utf8(result).unwrap(), $expected);
+
+                    let mut result = Vec::new();
+                    ircat_pango(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(String::from_utf8(result).unwrap(), $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", "foo bar"),
+        (
+            escape,
+            b"a < \"b\" && c > d",
+            "a &lt; &quot;b&quot; &amp;&amp; c &gt; d"
+        ),
+        (
+            color,
+            b"\x034&\x0f",
+            "<span foreground=\"#ff0000\">&amp;</span>"
+        ),
+        (
+            background,
+            b"\x034,2text",
+            "<span foreground=\"#ff0000\" background=\"#00007f\">text</span>"
+        ),
+        (newline, b"\x02bold\nplain\n", "<b>bold</b>\nplain\n"),
+        (
+            nesting,
+            b"\x034\x02\x1fboth\x02under",
+            "<span foreground=\"#ff0000\"><b><u>both</u></b></span>\
+             <span foreground=\"#ff0000\"><u>under</u></span>"
+        ),
+        (
+            reset,
+            b"\x1d\x11\x1ecode\x0fplain",
+            "<i><s><tt>code</tt></s></i>plain"
+        ),
+        (default_color, b"\x0399text", "text"),
+        (
+            reset_color_keeps_bold,
+            b"\x02\x034bold-red\x03still-bold",
+            "<span foreground=\"#ff0000\"><b>bold-red</b></span><b>still-bold</b>"
+        ),
+    );
+}
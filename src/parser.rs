@@ -0,0 +1,521 @@
+use alloc::vec::Vec;
+
+/// A text attribute toggled by an IRC formatting code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Attribute {
+    /// Bold (`\x02`).
+    Bold,
+    /// Italics (`\x1d`).
+    Italic,
+    /// Underline (`\x1f`).
+    Underline,
+    /// Strikethrough (`\x1e`).
+    Strikethrough,
+    /// Reversed colors (`\x16`).
+    Reverse,
+    /// Blinking text, from a byte chosen with
+    /// [`IrcatBuilder::blink_byte`](crate::IrcatBuilder::blink_byte) since IRC has no code for it.
+    Blink,
+}
+
+impl Attribute {
+    pub(crate) const ALL: [Self; 6] = [
+        Self::Bold,
+        Self::Italic,
+        Self::Underline,
+        Self::Strikethrough,
+        Self::Reverse,
+        Self::Blink,
+    ];
+
+    pub(crate) fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// What to do with the bell byte (`\x07`), which makes terminals beep.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Bell {
+    /// Pass it through unchanged.
+    #[default]
+    Keep,
+    /// Remove it.
+    Drop,
+    /// Replace it with the visible `␇` symbol.
+    Show,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Event<'a> {
+    Text(&'a [u8]),
+    /// A line ending byte, either `\n` or `\r`.
+    LineEnd(u8),
+    Toggle(Attribute),
+    Monospace,
+    Color(u8, Option<u8>),
+    HexColor(u32, Option<u32>),
+    /// A background color leaving the foreground unchanged.
+    Background(u8),
+    HexBackground(u32),
+    /// Colors given by their xterm 256 color index rather than as IRC colors.
+    XtermColor(u8, Option<u8>),
+    XtermBackground(u8),
+    ResetColor,
+    Reset,
+    /// A code that was not understood and is printed literally.
+    Unknown,
+    /// A complete ANSI control sequence found in the input.
+    Ansi(&'a [u8]),
+}
+
+/// The longest ANSI control sequence passed through, longer ones are dropped whole.
+const MAX_ANSI_LEN: usize = 64;
+
+/// Up to six hex digits of an RGB color.
+#[derive(Clone, Copy, Debug)]
+struct HexDigits {
+    digits: [u8; 6],
+    len: usize,
+}
+
+impl HexDigits {
+    fn new() -> Self {
+        Self {
+            digits: [0; 6],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, c: u8) {
+        self.digits[self.len] = c;
+        self.len += 1;
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == self.digits.len()
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.digits[..self.len]
+    }
+
+    fn value(&self) -> u32 {
+        self.as_bytes().iter().fold(0, |value, c| {
+            value << 4 | char::from(*c).to_digit(16).unwrap_or_default()
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    Normal,
+    Start,
+    Foreground1(u8),
+    Foreground2(u8),
+    Comma(u8),
+    Background1(u8, u8),
+    HexForeground(HexDigits),
+    HexForeground2(u32),
+    HexComma(u32),
+    HexBackground(u32, HexDigits),
+    /// After an escape byte.
+    Escape,
+    /// Inside an ANSI control sequence.
+    Csi,
+    /// Inside an ANSI control sequence too long to pass through.
+    LongCsi,
+}
+
+/// Splits IRC formatted bytes into text and formatting codes.
+///
+/// Colors after `\x03` are at most two digits for both the foreground and the background, leading
+/// zeros included, so any further digits are text: `\x030002` is color 0 followed by `02`. A
+/// `\x03` without a foreground resets both colors, so `\x03,9` is a reset followed by `,9`, and
+/// a comma without a background digit after it is text, so `\x032,x` is color 2 followed by `,x`.
+/// A code ends at the next formatting code, so `\x034\x03red` is color 4 then a reset of it.
+#[derive(Debug)]
+pub(crate) struct Parser {
+    state: State,
+    /// Whether ANSI control sequences are recognized and passed through whole.
+    ansi_passthrough: bool,
+    /// A byte resetting all formatting like `\x0f`.
+    reset_byte: Option<u8>,
+    /// A byte starting a color code like `\x03`.
+    color_byte: Option<u8>,
+    /// A byte toggling blink.
+    blink_byte: Option<u8>,
+    bell: Bell,
+    /// The ANSI control sequence being collected.
+    ansi: Vec<u8>,
+}
+
+impl Parser {
+    pub(crate) fn new(ansi_passthrough: bool) -> Self {
+        Self {
+            state: State::Normal,
+            ansi_passthrough,
+            reset_byte: None,
+            color_byte: None,
+            blink_byte: None,
+            bell: Bell::Keep,
+            ansi: Vec::new(),
+        }
+    }
+
+    /// Also reset all formatting at `reset_byte` unless it already has a meaning.
+    pub(crate) fn with_reset_byte(mut self, reset_byte: Option<u8>) -> Self {
+        self.reset_byte = reset_byte;
+        self
+    }
+
+    /// Also start a color code at `color_byte` unless it already has a meaning.
+    pub(crate) fn with_color_byte(mut self, color_byte: Option<u8>) -> Self {
+        self.color_byte = color_byte;
+        self
+    }
+
+    /// Toggle blink at `blink_byte` unless it already has a meaning.
+    pub(crate) fn with_blink_byte(mut self, blink_byte: Option<u8>) -> Self {
+        self.blink_byte = blink_byte;
+        self
+    }
+
+    /// Handle the bell byte as `bell` says.
+    pub(crate) fn with_bell(mut self, bell: Bell) -> Self {
+        self.bell = bell;
+        self
+    }
+
+    /// Return to the initial state discarding any partial code.
+    pub(crate) fn reset(&mut self) {
+        self.state = State::Normal;
+        self.ansi.clear();
+    }
+
+    pub(crate) fn parse(&mut self, input: &[u8], mut handle: impl FnMut(Event<'_>)) {
+        let mut i = 0;
+        while i < input.len() {
+            if let State::Normal = self.state {
+                let len = self.text_len(&input[i..]);
+                if len != 0 {
+                    handle(Event::Text(&input[i..i + len]));
+                    i += len;
+                    continue;
+                }
+            }
+            if self.step(input[i], &mut handle) {
+                i += 1;
+            }
+        }
+    }
+
+    /// Find the length of the plain text at the start of `input`.
+    fn text_len(&self, input: &[u8]) -> usize {
+        const WORD: usize = size_of::<u64>();
+        const LOW: u64 = u64::from_ne_bytes([0x01; WORD]);
+        const HIGH: u64 = u64::from_ne_bytes([0x80; WORD]);
+
+        let is_special = |c: u8| {
+            is_special(c)
+                || (self.ansi_passthrough && c == b'\x1b')
+                || Some(c) == self.reset_byte
+                || Some(c) == self.color_byte
+                || Some(c) == self.blink_byte
+                || (self.bell != Bell::Keep && c == b'\x07')
+        };
+        // Every special byte is below 0x20 so skip a word at a time while none of its bytes are.
+        let mut len = 0;
+        let words = if [self.reset_byte, self.color_byte, self.blink_byte]
+            .iter()
+            .flatten()
+            .any(|c| *c >= 0x20)
+        {
+            [].chunks_exact(WORD)
+        } else {
+            input.chunks_exact(WORD)
+        };
+        for chunk in words {
+            let mut bytes = [0; WORD];
+            bytes.copy_from_slice(chunk);
+            let word = u64::from_ne_bytes(bytes);
+            if word.wrapping_sub(LOW * 0x20) & !word & HIGH != 0 {
+                break;
+            }
+            len += WORD;
+        }
+        input[len..]
+            .iter()
+            .position(|c| is_special(*c))
+            .map_or(input.len(), |position| len + position)
+    }
+
+    /// Advance the state machine by `c` returning whether `c` was consumed.
+    fn step(&mut self, c: u8, handle: &mut impl FnMut(Event<'_>)) -> bool {
+        match self.state {
+            State::Normal => match c {
+                b'\x02' => handle(Event::Toggle(Attribute::Bold)),
+                b'\x03' => self.state = State::Start,
+                b'\x04' => self.state = State::HexForeground(HexDigits::new()),
+                b'\x0f' => handle(Event::Reset),
+                b'\x11' => handle(Event::Monospace),
+                b'\x16' => handle(Event::Toggle(Attribute::Reverse)),
+                b'\x1d' => handle(Event::Toggle(Attribute::Italic)),
+                b'\x1e' => handle(Event::Toggle(Attribute::Strikethrough)),
+                b'\x1f' => handle(Event::Toggle(Attribute::Underline)),
+                b'\n' | b'\r' => handle(Event::LineEnd(c)),
+                b'\x1b' if self.ansi_passthrough => self.state = State::Escape,
+                _ if Some(c) == self.color_byte => self.state = State::Start,
+                _ if Some(c) == self.reset_byte => handle(Event::Reset),
+                _ if Some(c) == self.blink_byte => handle(Event::Toggle(Attribute::Blink)),
+                b'\x07' if self.bell == Bell::Show => handle(Event::Text("\u{2407}".as_bytes())),
+                b'\x07' if self.bell == Bell::Drop => {}
+                _ => unreachable!("{c:#x} is not special"),
+            },
+
+            State::Start => match c {
+                b'0'..=b'9' => self.state = State::Foreground1(c - b'0'),
+                _ => {
+                    handle(Event::ResetColor);
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            State::Foreground1(fg_color) => match c {
+                b'0'..=b'9' => self.state = State::Foreground2(fg_color * 10 + c - b'0'),
+                b',' => self.state = State::Comma(fg_color),
+                _ => {
+                    handle(Event::Color(fg_color, None));
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            State::Foreground2(fg_color) => match c {
+                b',' => self.state = State::Comma(fg_color),
+                _ => {
+                    handle(Event::Color(fg_color, None));
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            State::Comma(fg_color) => match c {
+                b'0'..=b'9' => self.state = State::Background1(fg_color, c - b'0'),
+                _ => {
+                    handle(Event::Color(fg_color, None));
+                    handle(Event::Text(b","));
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            State::Background1(fg_color, bg_color) => match c {
+                b'0'..=b'9' => {
+                    handle(Event::Color(fg_color, Some(bg_color * 10 + c - b'0')));
+                    self.state = State::Normal;
+                }
+                _ => {
+                    handle(Event::Color(fg_color, Some(bg_color)));
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            State::HexForeground(_)
+            | State::HexForeground2(_)
+            | State::HexComma(_)
+            | State::HexBackground(_, _) => return self.step_hex(c, handle),
+
+            State::Escape | State::Csi | State::LongCsi => return self.step_ansi(c, handle),
+        }
+        true
+    }
+
+    fn step_ansi(&mut self, c: u8, handle: &mut impl FnMut(Event<'_>)) -> bool {
+        match self.state {
+            State::Escape => match c {
+                b'[' => {
+                    self.ansi.extend_from_slice(b"\x1b[");
+                    self.state = State::Csi;
+                }
+                _ => {
+                    handle(Event::Text(b"\x1b"));
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            State::Csi => match c {
+                // Parameter and intermediate bytes.
+                0x20..=0x3f if self.ansi.len() < MAX_ANSI_LEN => self.ansi.push(c),
+                0x20..=0x3f => {
+                    self.ansi.clear();
+                    self.state = State::LongCsi;
+                }
+                // The final byte.
+                0x40..=0x7e => {
+                    self.ansi.push(c);
+                    handle(Event::Ansi(&self.ansi));
+                    self.ansi.clear();
+                    self.state = State::Normal;
+                }
+                // Drop malformed sequences rather than let them swallow what follows.
+                _ => {
+                    self.ansi.clear();
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            // Skip to the final byte so none of the sequence shows as text.
+            State::LongCsi => match c {
+                0x20..=0x3f => {}
+                0x40..=0x7e => self.state = State::Normal,
+                _ => {
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            _ => unreachable!("not in an ANSI sequence"),
+        }
+        true
+    }
+
+    fn step_hex(&mut self, c: u8, handle: &mut impl FnMut(Event<'_>)) -> bool {
+        match self.state {
+            State::HexForeground(mut digits) => match c {
+                b'0'..=b'9' | b'a'..=b'f' | b'A'..=b'F' => {
+                    digits.push(c);
+                    self.state = if digits.is_full() {
+                        State::HexForeground2(digits.value())
+                    } else {
+                        State::HexForeground(digits)
+                    };
+                }
+                _ => {
+                    if digits.len == 0 {
+                        handle(Event::ResetColor);
+                    } else {
+                        handle(Event::Unknown);
+                        handle(Event::Text(digits.as_bytes()));
+                    }
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            State::HexForeground2(fg_color) => match c {
+                b',' => self.state = State::HexComma(fg_color),
+                _ => {
+                    handle(Event::HexColor(fg_color, None));
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            State::HexComma(fg_color) => match c {
+                b'0'..=b'9' | b'a'..=b'f' | b'A'..=b'F' => {
+                    let mut digits = HexDigits::new();
+                    digits.push(c);
+                    self.state = State::HexBackground(fg_color, digits);
+                }
+                _ => {
+                    handle(Event::HexColor(fg_color, None));
+                    handle(Event::Text(b","));
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            State::HexBackground(fg_color, mut digits) => match c {
+                b'0'..=b'9' | b'a'..=b'f' | b'A'..=b'F' => {
+                    digits.push(c);
+                    self.state = if digits.is_full() {
+                        handle(Event::HexColor(fg_color, Some(digits.value())));
+                        State::Normal
+                    } else {
+                        State::HexBackground(fg_color, digits)
+                    };
+                }
+                _ => {
+                    handle(Event::HexColor(fg_color, None));
+                    handle(Event::Text(b","));
+                    handle(Event::Unknown);
+                    handle(Event::Text(digits.as_bytes()));
+                    self.state = State::Normal;
+                    return false;
+                }
+            },
+
+            _ => unreachable!("not in a hex color"),
+        }
+        true
+    }
+
+    /// Emit any code left incomplete at the end of input.
+    pub(crate) fn finish(&mut self, mut handle: impl FnMut(Event<'_>)) {
+        match self.state {
+            State::Normal => {}
+            State::Start => handle(Event::ResetColor),
+            State::Foreground1(fg_color) | State::Foreground2(fg_color) => {
+                handle(Event::Color(fg_color, None));
+            }
+            State::Comma(fg_color) => {
+                handle(Event::Color(fg_color, None));
+                handle(Event::Text(b","));
+            }
+            State::Background1(fg_color, bg_color) => {
+                handle(Event::Color(fg_color, Some(bg_color)));
+            }
+            State::HexForeground(digits) => {
+                if digits.len == 0 {
+                    handle(Event::ResetColor);
+                } else {
+                    handle(Event::Unknown);
+                    handle(Event::Text(digits.as_bytes()));
+                }
+            }
+            State::HexForeground2(fg_color) => handle(Event::HexColor(fg_color, None)),
+            State::HexComma(fg_color) => {
+                handle(Event::HexColor(fg_color, None));
+                handle(Event::Text(b","));
+            }
+            State::HexBackground(fg_color, digits) => {
+                handle(Event::HexColor(fg_color, None));
+                handle(Event::Text(b","));
+                handle(Event::Unknown);
+                handle(Event::Text(digits.as_bytes()));
+            }
+            State::Escape => handle(Event::Text(b"\x1b")),
+            State::Csi => self.ansi.clear(),
+            State::LongCsi => {}
+        }
+        self.state = State::Normal;
+    }
+}
+
+/// Whether `input` contains any formatting code, as opposed to only text and line endings.
+pub(crate) fn has_formatting(input: &[u8]) -> bool {
+    input
+        .iter()
+        .any(|c| is_special(*c) && !matches!(c, b'\n' | b'\r'))
+}
+
+fn is_special(c: u8) -> bool {
+    matches!(
+        c,
+        b'\x02'
+            | b'\x03'
+            | b'\x04'
+            | b'\x0f'
+            | b'\x11'
+            | b'\x16'
+            | b'\x1d'
+            | b'\x1e'
+            | b'\x1f'
+            | b'\n'
+            | b'\r'
+    )
+}
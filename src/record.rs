@@ -0,0 +1,288 @@
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Result;
+use std::io::Write;
+
+use crate::filter::Filter;
+
+/// The tag of a frame holding IRC formatted input.
+const IRC: u8 = b'i';
+/// The tag of a frame holding the ANSI translation of the input before it.
+const ANSI: u8 = b'a';
+
+/// A frame of a recording made by a [`Recorder`].
+///
+/// A recording is a sequence of frames each made of a tag byte, `i` for IRC formatted input or
+/// `a` for ANSI output, the length of the payload as a 32-bit big-endian integer, and the payload
+/// itself. Each input frame is followed by an output frame with its translation, and a flush adds
+/// an output frame closing any formatting left open.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Frame {
+    /// IRC formatted input exactly as it was written.
+    Irc(Vec<u8>),
+    /// The ANSI translation of the input so far.
+    Ansi(Vec<u8>),
+}
+
+impl Frame {
+    /// Read the next frame from `reader` or `None` at the end of the recording.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error reading from `reader`, [`ErrorKind::UnexpectedEof`] if the recording ends
+    /// inside a frame, and [`ErrorKind::InvalidData`] for a frame with an unknown tag.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Option<Self>> {
+        let mut tag = [0];
+        loop {
+            match reader.read(&mut tag) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let len = usize::try_from(u32::from_be_bytes(len))
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let mut payload = Vec::new();
+        let read = reader.take(len as u64).read_to_end(&mut payload)?;
+        if read != len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        match tag[0] {
+            IRC => Ok(Some(Self::Irc(payload))),
+            ANSI => Ok(Some(Self::Ansi(payload))),
+            tag => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown frame tag {tag:#x}"),
+            )),
+        }
+    }
+
+    /// Write this frame to `writer`.
+    ///
+    /// A payload too long for one frame is split across several frames of the same kind.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error writing to `writer`.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            Self::Irc(payload) => write_frame(writer, IRC, payload),
+            Self::Ansi(payload) => write_frame(writer, ANSI, payload),
+        }
+    }
+}
+
+fn write_frame<W: Write>(writer: &mut W, tag: u8, payload: &[u8]) -> Result<()> {
+    let mut chunks = payload.chunks(u32::MAX as usize);
+    // An empty payload still gets a frame.
+    let first = chunks.next().unwrap_or_default();
+    for chunk in [first].into_iter().chain(chunks) {
+        writer.write_all(&[tag])?;
+        writer.write_all(&u32::try_from(chunk.len()).unwrap_or(u32::MAX).to_be_bytes())?;
+        writer.write_all(chunk)?;
+    }
+    Ok(())
+}
+
+/// Adapts a [`Write`] by recording everything written to it alongside its translation through a
+/// [`Filter`].
+///
+/// Keeping the input makes the recording lossless so it can be [replayed](replay) through a
+/// differently configured filter, such as one with another palette. See [`Frame`] for the format.
+///
+/// # Examples
+///
+/// ```
+/// # use std::io::Write;
+/// #
+/// # use ircat::replay;
+/// # use ircat::IrcatBuilder;
+/// # use ircat::IrcatFilter;
+/// # use ircat::Palette;
+/// # use ircat::Recorder;
+/// #
+/// let mut recorder = Recorder::<IrcatFilter, _>::new(Vec::new());
+/// recorder.write_all(b"\x034red")?;
+/// recorder.flush()?;
+/// let recording = recorder.into_inner();
+///
+/// let mut palette = Palette::default();
+/// palette.set(4, "8;5;196");
+/// let mut filter = IrcatBuilder::default().palette(palette).build();
+/// let mut output = Vec::new();
+/// replay(recording.as_slice(), &mut filter, &mut output)?;
+/// assert_eq!(output, b"\x1b[38;5;196mred\x1b[39m");
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug)]
+pub struct Recorder<F: Filter, W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+    filter: F,
+}
+
+impl<F: Filter, W: Write> Recorder<F, W> {
+    /// Create a new `Recorder` writing to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self::with_filter(inner, F::init())
+    }
+
+    /// Create a new `Recorder` writing to `inner` through an already constructed `filter`.
+    pub fn with_filter(inner: W, filter: F) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+            filter,
+        }
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwrap this `Recorder` returning the underlying writer.
+    ///
+    /// Nothing is flushed so the output closing any formatting left open is not recorded.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<F: Filter, W: Write> Write for Recorder<F, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.buffer.clear();
+        self.filter.filter(buf, &mut self.buffer);
+        write_frame(&mut self.inner, IRC, buf)?;
+        write_frame(&mut self.inner, ANSI, &self.buffer)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.buffer.clear();
+        self.filter.finish(&mut self.buffer);
+        if !self.buffer.is_empty() {
+            write_frame(&mut self.inner, ANSI, &self.buffer)?;
+        }
+        self.inner.flush()
+    }
+}
+
+/// Translate the input recorded in `reader` by a [`Recorder`] again through `filter` into
+/// `writer`.
+///
+/// The recorded output is skipped, only the input is translated. Formatting left open at the end
+/// is closed. Returns the number of bytes written.
+///
+/// # Errors
+///
+/// Returns any error reading a [`Frame`] or writing to `writer`.
+pub fn replay<F: Filter, R: Read, W: Write>(
+    mut reader: R,
+    filter: &mut F,
+    writer: &mut W,
+) -> Result<u64> {
+    let mut output = Vec::new();
+    let mut written = 0;
+    loop {
+        output.clear();
+        match Frame::read(&mut reader)? {
+            Some(Frame::Irc(input)) => filter.filter(&input, &mut output),
+            Some(Frame::Ansi(_)) => continue,
+            None => {
+                filter.finish(&mut output);
+                writer.write_all(&output)?;
+                return Ok(written + output.len() as u64);
+            }
+        }
+        writer.write_all(&output)?;
+        written += output.len() as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::io::Result;
+    use std::io::Write;
+
+    use super::Frame;
+    use crate::replay;
+    use crate::IrcatBuilder;
+    use crate::IrcatFilter;
+    use crate::OutputFormat;
+    use crate::Recorder;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let mut recorder = Recorder::<IrcatFilter, _>::new(Vec::new());
+        recorder.write_all(b"\x034,2red ")?;
+        recorder.write_all(b"\x02bold\n\x03")?;
+        recorder.write_all(b"1")?;
+        recorder.write_all(b"x")?;
+        recorder.flush()?;
+        let recording = recorder.into_inner();
+
+        let mut frames = Vec::new();
+        let mut reader = recording.as_slice();
+        while let Some(frame) = Frame::read(&mut reader)? {
+            frames.push(frame);
+        }
+        assert_eq!(
+            frames,
+            [
+                Frame::Irc(b"\x034,2red ".to_vec()),
+                Frame::Ansi(b"\x1b[31m\x1b[44mred ".to_vec()),
+                Frame::Irc(b"\x02bold\n\x03".to_vec()),
+                Frame::Ansi(b"\x1b[1mbold\x1b[39m\x1b[49m\x1b[22m\n".to_vec()),
+                Frame::Irc(b"1".to_vec()),
+                // The color may go on with another digit.
+                Frame::Ansi(Vec::new()),
+                Frame::Irc(b"x".to_vec()),
+                Frame::Ansi(b"\x1b[38;5;235mx".to_vec()),
+                Frame::Ansi(b"\x1b[39m".to_vec()),
+            ]
+        );
+
+        let mut rewritten = Vec::new();
+        for frame in &frames {
+            frame.write(&mut rewritten)?;
+        }
+        assert_eq!(rewritten, recording);
+
+        let mut output = Vec::new();
+        let written = replay(
+            recording.as_slice(),
+            &mut IrcatBuilder::default().output(OutputFormat::Html).build(),
+            &mut output,
+        )?;
+        assert_eq!(
+            output,
+            b"<span style=\"color:#ff0000;background-color:#00007f\">red </span>\
+              <span style=\"color:#ff0000;background-color:#00007f;font-weight:bold\">bold</span>\
+              \n<span style=\"color:#000000\">x</span>"
+        );
+        assert_eq!(written, output.len() as u64);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated() {
+        for (recording, kind) in [
+            (b"i\x00\x00".as_ref(), ErrorKind::UnexpectedEof),
+            (b"a\x00\x00\x00\x05abc", ErrorKind::UnexpectedEof),
+            (b"x\x00\x00\x00\x00", ErrorKind::InvalidData),
+        ] {
+            let result = Frame::read(&mut { recording });
+            assert!(
+                matches!(&result, Err(e) if e.kind() == kind),
+                "{}: {result:?}",
+                recording.escape_ascii()
+            );
+        }
+    }
+}
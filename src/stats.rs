@@ -0,0 +1,76 @@
+use crate::parser::Event;
+use crate::parser::Parser;
+
+/// Counts of the formatting codes found while translating.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Codes setting a foreground color.
+    pub foreground: u64,
+    /// Codes setting a background color.
+    pub background: u64,
+    /// Codes resetting the colors or all formatting.
+    pub resets: u64,
+    /// Codes that were not understood and printed literally.
+    pub unknown: u64,
+}
+
+impl Stats {
+    pub(crate) fn count(&mut self, event: Event<'_>) {
+        match event {
+            Event::Color(_, bg_color) => {
+                self.foreground += 1;
+                self.background += u64::from(bg_color.is_some());
+            }
+            Event::HexColor(_, bg_color) => {
+                self.foreground += 1;
+                self.background += u64::from(bg_color.is_some());
+            }
+            Event::XtermColor(_, bg_color) => {
+                self.foreground += 1;
+                self.background += u64::from(bg_color.is_some());
+            }
+            Event::Background(_) | Event::HexBackground(_) | Event::XtermBackground(_) => {
+                self.background += 1;
+            }
+            Event::ResetColor | Event::Reset => self.resets += 1,
+            Event::Unknown => self.unknown += 1,
+            Event::Text(_)
+            | Event::LineEnd(_)
+            | Event::Toggle(_)
+            | Event::Monospace
+            | Event::Ansi(_) => {}
+        }
+    }
+}
+
+/// Count how often each IRC color is set in `input`, as a foreground or a background.
+///
+/// Nothing is translated so this is a cheap way to find which palette entries a log relies on.
+/// Color 99, the default color, is counted like the others while hex colors are not counted.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::color_histogram;
+/// #
+/// let histogram = color_histogram(b"\x034red \x034,2on blue \x0302blue");
+/// assert_eq!((histogram[4], histogram[2], histogram[3]), (2, 2, 0));
+/// ```
+#[must_use]
+pub fn color_histogram(input: &[u8]) -> [u32; 100] {
+    let mut histogram = [0; 100];
+    let mut count = |event: Event<'_>| match event {
+        Event::Color(fg_color, bg_color) => {
+            histogram[usize::from(fg_color)] += 1;
+            if let Some(bg_color) = bg_color {
+                histogram[usize::from(bg_color)] += 1;
+            }
+        }
+        Event::Background(bg_color) => histogram[usize::from(bg_color)] += 1,
+        _ => {}
+    };
+    let mut parser = Parser::new(false);
+    parser.parse(input, &mut count);
+    parser.finish(&mut count);
+    histogram
+}
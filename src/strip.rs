@@ -0,0 +1,51 @@
+use alloc::vec::Vec;
+
+use crate::parser::Event;
+
+/// Renders only the text of parsed IRC formatting.
+pub(crate) fn render(event: Event<'_>, output: &mut Vec<u8>) {
+    match event {
+        Event::Text(text) => output.extend_from_slice(text),
+        Event::LineEnd(c) => output.push(c),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+
+    use crate::strip;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let mut result = Vec::new();
+                    strip(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    strip(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", b"foo bar"),
+        (colors, b"\x032,3blue\x02bold", b"bluebold"),
+        (
+            attributes,
+            b"\x1ditalic\x1d \x1funderline\x0f\n",
+            b"italic underline\n"
+        ),
+        (reset_color, b"\x034red\x03,plain", b"red,plain"),
+        (unterminated_comma, b"\x034,", b","),
+        (hex, b"\x04ff8000,00007ftext", b"text"),
+        (hex_short, b"\x04ff8x", b"ff8x"),
+    );
+}
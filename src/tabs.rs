@@ -0,0 +1,123 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::parser::Event;
+use crate::width::width;
+use crate::width::Whole;
+
+/// Expands tabs in the text to spaces up to the next tab stop.
+///
+/// Columns are counted in the display width of the text only so formatting codes, which take no
+/// space, do not move the tab stops. Wide characters take two columns and combining marks none.
+#[derive(Debug)]
+pub(crate) struct Tabs {
+    /// Spaces for a whole tab.
+    spaces: Vec<u8>,
+    column: usize,
+    /// A character split across texts, kept only to count its width.
+    whole: Whole,
+}
+
+impl Tabs {
+    pub(crate) fn new(width: usize) -> Self {
+        Self {
+            spaces: vec![b' '; width],
+            column: 0,
+            whole: Whole::default(),
+        }
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.spaces.len()
+    }
+
+    pub(crate) fn process(&mut self, event: Event<'_>, handle: &mut impl FnMut(Event<'_>)) {
+        match event {
+            Event::Text(mut text) => {
+                while let Some(i) = text.iter().position(|c| *c == b'\t') {
+                    self.text(&text[..i], handle);
+                    self.whole.finish(|text| self.column += width(text));
+                    let len = self.spaces.len() - self.column % self.spaces.len();
+                    handle(Event::Text(&self.spaces[..len]));
+                    self.column += len;
+                    text = &text[i + 1..];
+                }
+                self.text(text, handle);
+            }
+            Event::LineEnd(_) => {
+                self.whole = Whole::default();
+                self.column = 0;
+                handle(event);
+            }
+            _ => handle(event),
+        }
+    }
+
+    fn text(&mut self, text: &[u8], handle: &mut impl FnMut(Event<'_>)) {
+        if !text.is_empty() {
+            self.whole.process(text, |text| self.column += width(text));
+            handle(Event::Text(text));
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+
+    use crate::IrcatBuilder;
+
+    macro_rules! tests {
+        ($(($name: ident, $width: expr, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let builder = IrcatBuilder::default().expand_tabs($width);
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (off, 0, b"a\tb", b"a\tb"),
+        (none, 4, b"foo bar", b"foo bar"),
+        (start, 4, b"\tx", b"    x"),
+        (stops, 4, b"a\tbc\tdefg\th", b"a   bc  defg    h"),
+        (width, 8, b"ab\tc", b"ab      c"),
+        (
+            colors,
+            4,
+            b"\x034ab\x03\tc\x032,3d\te",
+            b"\x1b[31mab\x1b[39m  c\x1b[34m\x1b[42md  e\x1b[39m\x1b[49m"
+        ),
+        (
+            codes_only,
+            4,
+            b"\x02\x1f\tx\x0f",
+            b"\x1b[1m\x1b[4m    x\x1b[0m"
+        ),
+        (lines, 4, b"abc\td\nab\tc\r\tx", b"abc d\nab  c\r    x"),
+        (utf8, 4, "é\tx".as_bytes(), "é   x".as_bytes()),
+        (
+            wide,
+            4,
+            "日本\tx\t語\ty".as_bytes(),
+            "日本    x   語  y".as_bytes()
+        ),
+        (
+            combining,
+            4,
+            "e\u{301}a\tx".as_bytes(),
+            "e\u{301}a  x".as_bytes()
+        ),
+        (invalid, 4, b"\xe6\t\x97x\ty", b"\xe6   \x97x  y"),
+    );
+}
@@ -0,0 +1,128 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::parser::Event;
+
+/// Appends a timestamp to the buffer it is given.
+type WriteTimestamp = dyn Fn(&mut Vec<u8>) + Send + Sync;
+
+/// Writes a timestamp at the start of every line.
+///
+/// This sits before wrapping so the timestamp counts towards the width of its line and lines broken
+/// by wrapping continue without one. It comes before any formatting of the line.
+#[derive(Clone)]
+pub(crate) struct Timestamps {
+    /// Appends the timestamp to the buffer.
+    write: Arc<WriteTimestamp>,
+    /// Whether nothing has been rendered on the current line yet.
+    line_start: bool,
+    buffer: Vec<u8>,
+}
+
+impl Timestamps {
+    pub(crate) fn new(write: impl Fn(&mut Vec<u8>) + Send + Sync + 'static) -> Self {
+        Self {
+            write: Arc::new(write),
+            line_start: true,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub(crate) fn process(&mut self, event: Event<'_>, handle: &mut impl FnMut(Event<'_>)) {
+        if self.line_start {
+            self.line_start = false;
+            self.buffer.clear();
+            (self.write)(&mut self.buffer);
+            if !self.buffer.is_empty() {
+                handle(Event::Text(&self.buffer));
+            }
+        }
+        // A `\r` before the `\n` is still part of the line.
+        if let Event::LineEnd(b'\n') = event {
+            self.line_start = true;
+        }
+        handle(event);
+    }
+
+    /// Start a new line.
+    pub(crate) fn reset(&mut self) {
+        self.line_start = true;
+    }
+}
+
+impl fmt::Debug for Timestamps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timestamps")
+            .field("line_start", &self.line_start)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+    use std::io::Result;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use crate::IrcatBuilder;
+    use crate::OutputFormat;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let builder = IrcatBuilder::default()
+                        .timestamp(|output| output.extend_from_slice(b"[12:34:56] "));
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (empty, b"", b""),
+        (none, b"foo bar", b"[12:34:56] foo bar"),
+        (
+            lines,
+            b"one\ntwo\r\n\nthree\n",
+            b"[12:34:56] one\n[12:34:56] two\r\n[12:34:56] \n[12:34:56] three\n"
+        ),
+        (
+            colors,
+            b"\x034red\n\x02\x032,3bold\x03\n",
+            b"[12:34:56] \x1b[31mred\x1b[39m\n[12:34:56] \x1b[1m\x1b[34m\x1b[42mbold\x1b[39m\x1b[49m\
+              \x1b[22m\n"
+        ),
+    );
+
+    #[test]
+    fn clock() -> Result<()> {
+        let clock = Arc::new(AtomicU32::new(0));
+        let ticks = Arc::clone(&clock);
+        let builder = IrcatBuilder::default()
+            .output(OutputFormat::Html)
+            .timestamp(move |output| {
+                let tick = ticks.fetch_add(1, Ordering::Relaxed);
+                output.extend_from_slice(format!("<{tick}> ").as_bytes());
+            });
+        let mut result = Vec::new();
+        builder.run(&mut BufReader::new(b"\x034a\nb\n".as_ref()), &mut result)?;
+        assert_eq!(
+            result,
+            b"&lt;0&gt; <span style=\"color:#ff0000\">a</span>\n&lt;1&gt; b\n"
+        );
+        assert_eq!(clock.load(Ordering::Relaxed), 2);
+        Ok(())
+    }
+}
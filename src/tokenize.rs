@@ -0,0 +1,234 @@
+use crate::color::xterm_rgb;
+use crate::parser::Attribute;
+use crate::parser::Event;
+use crate::parser::Parser;
+
+/// A piece of IRC formatted text produced by a [`Tokenizer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// Text without any formatting codes.
+    Text(&'a [u8]),
+    /// A line ending byte, either `\n` or `\r`.
+    LineEnd(u8),
+    /// Toggle bold (`\x02`).
+    Bold,
+    /// Toggle italics (`\x1d`).
+    Italic,
+    /// Toggle underline (`\x1f`).
+    Underline,
+    /// Toggle strikethrough (`\x1e`).
+    Strikethrough,
+    /// Toggle reversed colors (`\x16`).
+    Reverse,
+    /// Toggle blink (the byte chosen with
+    /// [`IrcatBuilder::blink_byte`](crate::IrcatBuilder::blink_byte)).
+    Blink,
+    /// Toggle monospace (`\x11`).
+    Monospace,
+    /// Set the foreground to an IRC color where 99 is the default color.
+    Foreground(u8),
+    /// Set the background to an IRC color where 99 is the default color.
+    Background(u8),
+    /// Set the foreground to an RGB color.
+    HexForeground(u32),
+    /// Set the background to an RGB color.
+    HexBackground(u32),
+    /// Reset both colors (`\x03` or `\x04` without a color).
+    ResetColor,
+    /// Reset all formatting (`\x0f`).
+    Reset,
+}
+
+/// Splits IRC formatted bytes into [`Token`]s.
+///
+/// This uses the same grammar as [`IrcatFilter`](crate::IrcatFilter) so custom renderers see
+/// exactly what the built-in ones do. Input can be handed over in arbitrarily sized chunks, a
+/// code split across chunks produces its tokens once it is complete. Codes that are not
+/// understood come out as text.
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::Token;
+/// # use ircat::Tokenizer;
+/// #
+/// let mut tokens = Vec::new();
+/// let mut tokenizer = Tokenizer::new();
+/// tokenizer.tokenize(b"\x034,2red\x0f\x02", |token| tokens.push(format!("{token:?}")));
+/// tokenizer.finish(|token| tokens.push(format!("{token:?}")));
+/// assert_eq!(
+///     tokens,
+///     ["Foreground(4)", "Background(2)", "Text([114, 101, 100])", "Reset", "Bold"],
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Tokenizer {
+    parser: Parser,
+}
+
+impl Tokenizer {
+    /// Create a tokenizer in its initial state.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            parser: Parser::new(false),
+        }
+    }
+
+    /// Split the next chunk of `input` calling `handle` with each token.
+    pub fn tokenize(&mut self, input: &[u8], mut handle: impl FnMut(Token<'_>)) {
+        self.parser.parse(input, |event| emit(event, &mut handle));
+    }
+
+    /// Call `handle` with the tokens of any code left incomplete at the end of the input.
+    ///
+    /// Afterwards the tokenizer is ready for new input.
+    pub fn finish(&mut self, mut handle: impl FnMut(Token<'_>)) {
+        self.parser.finish(|event| emit(event, &mut handle));
+    }
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn emit(event: Event<'_>, handle: &mut impl FnMut(Token<'_>)) {
+    match event {
+        Event::Text(text) => handle(Token::Text(text)),
+        Event::LineEnd(c) => handle(Token::LineEnd(c)),
+        Event::Toggle(attribute) => handle(match attribute {
+            Attribute::Bold => Token::Bold,
+            Attribute::Italic => Token::Italic,
+            Attribute::Underline => Token::Underline,
+            Attribute::Strikethrough => Token::Strikethrough,
+            Attribute::Reverse => Token::Reverse,
+            Attribute::Blink => Token::Blink,
+        }),
+        Event::Monospace => handle(Token::Monospace),
+        Event::Color(fg_color, bg_color) => {
+            handle(Token::Foreground(fg_color));
+            if let Some(bg_color) = bg_color {
+                handle(Token::Background(bg_color));
+            }
+        }
+        Event::HexColor(fg_color, bg_color) => {
+            handle(Token::HexForeground(fg_color));
+            if let Some(bg_color) = bg_color {
+                handle(Token::HexBackground(bg_color));
+            }
+        }
+        Event::Background(bg_color) => handle(Token::Background(bg_color)),
+        Event::HexBackground(bg_color) => handle(Token::HexBackground(bg_color)),
+        Event::XtermColor(fg_color, bg_color) => {
+            handle(Token::HexForeground(xterm_rgb(fg_color)));
+            if let Some(bg_color) = bg_color {
+                handle(Token::HexBackground(xterm_rgb(bg_color)));
+            }
+        }
+        Event::XtermBackground(bg_color) => handle(Token::HexBackground(xterm_rgb(bg_color))),
+        Event::ResetColor => handle(Token::ResetColor),
+        Event::Reset => handle(Token::Reset),
+        // The text of the code follows as its own token.
+        Event::Unknown => {}
+        // Passing ANSI sequences through is never enabled.
+        Event::Ansi(sequence) => handle(Token::Text(sequence)),
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use crate::Token;
+    use crate::Tokenizer;
+
+    /// Tokenize `input` in chunks of `size` describing each token with adjacent text merged.
+    fn tokens(input: &[u8], size: usize) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut text = Vec::new();
+        let mut push = |token: Token<'_>| match token {
+            Token::Text(t) => text.extend_from_slice(t),
+            token => {
+                if !text.is_empty() {
+                    tokens.push(format!("Text({:?})", String::from_utf8_lossy(&text)));
+                    text.clear();
+                }
+                tokens.push(format!("{token:?}"));
+            }
+        };
+        let mut tokenizer = Tokenizer::new();
+        for chunk in input.chunks(size) {
+            tokenizer.tokenize(chunk, &mut push);
+        }
+        tokenizer.finish(&mut push);
+        if !text.is_empty() {
+            tokens.push(format!("Text({:?})", String::from_utf8_lossy(&text)));
+        }
+        tokens
+    }
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!(tokens($input, $input.len().max(1)), $expected);
+                    assert_eq!(tokens($input, 1), $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (empty, b"", [""; 0]),
+        (none, b"foo bar", ["Text(\"foo bar\")"]),
+        (
+            mixed,
+            b"\x02bold\x02 \x034,12blue\x03 \x1d\x1f\x1e\x16\x11x\x0f\r\n",
+            [
+                "Bold",
+                "Text(\"bold\")",
+                "Bold",
+                "Text(\" \")",
+                "Foreground(4)",
+                "Background(12)",
+                "Text(\"blue\")",
+                "ResetColor",
+                "Text(\" \")",
+                "Italic",
+                "Underline",
+                "Strikethrough",
+                "Reverse",
+                "Monospace",
+                "Text(\"x\")",
+                "Reset",
+                "LineEnd(13)",
+                "LineEnd(10)",
+            ]
+        ),
+        (
+            hex,
+            b"\x04ff0000,00Ff00a\x04123456b\x04",
+            [
+                "HexForeground(16711680)",
+                "HexBackground(65280)",
+                "Text(\"a\")",
+                "HexForeground(1193046)",
+                "Text(\"b\")",
+                "ResetColor",
+            ]
+        ),
+        (
+            invalid,
+            b"\x03,2\x04ff x\x034,",
+            [
+                "ResetColor",
+                "Text(\",2ff x\")",
+                "Foreground(4)",
+                "Text(\",\")",
+            ]
+        ),
+        (escape, b"\x1b[1m", ["Text(\"\\u{1b}[1m\")"]),
+    );
+}
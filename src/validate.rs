@@ -0,0 +1,307 @@
+use core::fmt;
+
+use crate::parser::Event;
+use crate::parser::Parser;
+
+/// The first problem [`validate`] found in its input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatError {
+    /// A `\x03` or `\x04` color code at `offset` ends the input so was likely cut short.
+    Dangling {
+        /// The offset of the color code.
+        offset: usize,
+    },
+    /// The hex color code at `offset` has fewer than six digits.
+    IncompleteHexColor {
+        /// The offset of the `\x04` starting the code.
+        offset: usize,
+    },
+    /// The control byte at `offset` is not an IRC formatting code.
+    UnknownControl {
+        /// The offset of the byte.
+        offset: usize,
+        /// The byte itself.
+        byte: u8,
+    },
+}
+
+impl FormatError {
+    /// The byte offset in the input of the code at fault.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        match *self {
+            Self::Dangling { offset }
+            | Self::IncompleteHexColor { offset }
+            | Self::UnknownControl { offset, .. } => offset,
+        }
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Dangling { offset } => write!(f, "color code at end of input at byte {offset}"),
+            Self::IncompleteHexColor { offset } => {
+                write!(f, "incomplete hex color at byte {offset}")
+            }
+            Self::UnknownControl { offset, byte } => {
+                write!(f, "unknown control byte {byte:#04x} at byte {offset}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FormatError {}
+
+/// An error translating in strict mode, see [`ircat_strict`](crate::ircat_strict).
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum IrcatError {
+    /// Reading or writing failed.
+    Io(std::io::Error),
+    /// The input has a malformed or unknown code.
+    Format(FormatError),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for IrcatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Format(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IrcatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Format(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for IrcatError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<FormatError> for IrcatError {
+    fn from(e: FormatError) -> Self {
+        Self::Format(e)
+    }
+}
+
+/// Check that `input` only contains well formed IRC formatting codes.
+///
+/// This runs the same grammar as translating but produces no output.
+///
+/// # Errors
+///
+/// Returns the first malformed or unknown code found, see [`FormatError`].
+///
+/// # Examples
+///
+/// ```
+/// # use ircat::validate;
+/// # use ircat::FormatError;
+/// #
+/// assert_eq!(validate(b"\x034red\x03 and \x02bold\x02\n"), Ok(()));
+/// assert_eq!(
+///     validate(b"\x04ff00 red"),
+///     Err(FormatError::IncompleteHexColor { offset: 0 }),
+/// );
+/// ```
+pub fn validate(input: &[u8]) -> Result<(), FormatError> {
+    let mut validator = Validator::new();
+    validator.feed(input)?;
+    validator.finish()
+}
+
+/// Checks input handed over in chunks like [`validate`] does all at once.
+#[derive(Debug)]
+pub(crate) struct Validator {
+    parser: Parser,
+    /// The offset of the next byte.
+    offset: usize,
+    /// Where the color code being parsed started.
+    start: usize,
+    /// The last byte seen.
+    last: Option<u8>,
+}
+
+impl Validator {
+    pub(crate) fn new() -> Self {
+        Self {
+            parser: Parser::new(false),
+            offset: 0,
+            start: 0,
+            last: None,
+        }
+    }
+
+    /// Check the next chunk of input.
+    pub(crate) fn feed(&mut self, input: &[u8]) -> Result<(), FormatError> {
+        let mut error = None;
+        // Feed the parser a byte at a time so it is known where each code starts.
+        for (i, &c) in input.iter().enumerate() {
+            let offset = self.offset + i;
+            let start = self.start;
+            self.parser
+                .parse(&input[i..=i], |event| check(event, start, &mut error));
+            if let Some(error) = error {
+                return Err(error);
+            }
+            if is_unknown_control(c) {
+                return Err(FormatError::UnknownControl { offset, byte: c });
+            }
+            // Neither byte can continue a code so each always starts a new one.
+            if matches!(c, b'\x03' | b'\x04') {
+                self.start = offset;
+            }
+        }
+        self.offset += input.len();
+        self.last = input.last().copied().or(self.last);
+        Ok(())
+    }
+
+    /// Check what is left once the input is exhausted.
+    pub(crate) fn finish(&mut self) -> Result<(), FormatError> {
+        let mut error = None;
+        let start = self.start;
+        self.parser.finish(|event| check(event, start, &mut error));
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        match self.last {
+            Some(b'\x03' | b'\x04') => Err(FormatError::Dangling {
+                offset: self.offset - 1,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Record an error for an unknown code starting at `start`.
+fn check(event: Event<'_>, start: usize, error: &mut Option<FormatError>) {
+    if let Event::Unknown = event {
+        error.get_or_insert(FormatError::IncompleteHexColor { offset: start });
+    }
+}
+
+/// Whether `c` is a control byte without meaning in IRC formatted text.
+fn is_unknown_control(c: u8) -> bool {
+    c.is_ascii_control()
+        && !matches!(
+            c,
+            b'\x02'
+                | b'\x03'
+                | b'\x04'
+                | b'\x0f'
+                | b'\x11'
+                | b'\x16'
+                | b'\x1d'
+                | b'\x1e'
+                | b'\x1f'
+                | b'\t'
+                | b'\n'
+                | b'\r'
+        )
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use crate::validate;
+    use crate::FormatError;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    assert_eq!(validate($input), $expected);
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", Ok(())),
+        (empty, b"", Ok(())),
+        (
+            formatting,
+            b"\x02\x1d\x1f\x1e\x11\x16bold\x0f\ttab\r\n",
+            Ok(())
+        ),
+        (colors, b"\x034red\x034,12blue\x0399,99\x03 none\n", Ok(())),
+        (hex, b"\x04ff0000red\x04ff0000,00ff00green\x04 none", Ok(())),
+        (line_end_reset, b"\x034red\x03\n", Ok(())),
+        (
+            dangling,
+            b"\x034red\x03",
+            Err(FormatError::Dangling { offset: 5 })
+        ),
+        (
+            dangling_hex,
+            b"red\x04",
+            Err(FormatError::Dangling { offset: 3 })
+        ),
+        (
+            incomplete_hex,
+            b"ok \x04ff00 red",
+            Err(FormatError::IncompleteHexColor { offset: 3 })
+        ),
+        (
+            incomplete_hex_at_end,
+            b"\x034ok \x04ff",
+            Err(FormatError::IncompleteHexColor { offset: 5 })
+        ),
+        (
+            incomplete_hex_background,
+            b"\x04ff0000,00ff red",
+            Err(FormatError::IncompleteHexColor { offset: 0 })
+        ),
+        (
+            incomplete_hex_before_color,
+            b"\x04ff\x034red",
+            Err(FormatError::IncompleteHexColor { offset: 0 })
+        ),
+        (
+            unknown_control,
+            b"bell\x07",
+            Err(FormatError::UnknownControl {
+                offset: 4,
+                byte: 0x07
+            })
+        ),
+        (
+            escape,
+            b"\x034\x1b[1m",
+            Err(FormatError::UnknownControl {
+                offset: 2,
+                byte: 0x1b
+            })
+        ),
+    );
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            FormatError::UnknownControl {
+                offset: 4,
+                byte: 0x07
+            }
+            .to_string(),
+            "unknown control byte 0x07 at byte 4"
+        );
+        assert_eq!(FormatError::Dangling { offset: 3 }.offset(), 3);
+    }
+}
@@ -0,0 +1,442 @@
+use alloc::vec::Vec;
+
+use crate::parser::Attribute;
+use crate::parser::Event;
+
+/// A WeeChat color as it maps onto IRC colors.
+#[derive(Clone, Copy, Debug)]
+enum Color {
+    /// One of WeeChat's basic colors translated to the closest IRC color.
+    Irc(u8),
+    /// An extended color given by its xterm 256 color index.
+    Xterm(u8),
+}
+
+/// A complete formatting code.
+#[derive(Clone, Copy, Debug)]
+enum Code {
+    Color {
+        attributes: u8,
+        fg_color: Option<Color>,
+        bg_color: Option<Color>,
+    },
+    ResetColor,
+    SetAttribute(Attribute),
+    RemoveAttribute(Attribute),
+}
+
+/// The result of decoding the bytes of a possibly incomplete code.
+enum Decode {
+    /// More bytes are needed to tell.
+    Incomplete,
+    /// The bytes do not form a supported code.
+    Invalid,
+    /// A code spanning the given number of bytes.
+    Complete(usize, Code),
+}
+
+/// Splits WeeChat formatted bytes into text and formatting codes.
+///
+/// See [`InputFormat::WeeChat`](crate::InputFormat::WeeChat) for the supported subset.
+#[derive(Debug)]
+pub(crate) struct WeeChatParser {
+    /// The bytes of the code being decoded.
+    pending: Vec<u8>,
+    attributes: u8,
+}
+
+impl WeeChatParser {
+    pub(crate) fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            attributes: 0,
+        }
+    }
+
+    pub(crate) fn parse(&mut self, input: &[u8], mut handle: impl FnMut(Event<'_>)) {
+        self.parse_inner(input, false, &mut handle);
+    }
+
+    /// Emit any code left incomplete at the end of input.
+    pub(crate) fn finish(&mut self, mut handle: impl FnMut(Event<'_>)) {
+        while !self.pending.is_empty() {
+            self.decode(true, &mut handle);
+        }
+    }
+
+    fn parse_inner(&mut self, input: &[u8], eof: bool, handle: &mut impl FnMut(Event<'_>)) {
+        let mut i = 0;
+        while i < input.len() {
+            if !self.pending.is_empty() {
+                self.pending.push(input[i]);
+                i += 1;
+                self.decode(eof, handle);
+                continue;
+            }
+
+            let len = input[i..]
+                .iter()
+                .position(|c| is_special(*c))
+                .unwrap_or(input.len() - i);
+            if len != 0 {
+                handle(Event::Text(&input[i..i + len]));
+                i += len;
+                continue;
+            }
+
+            match input[i] {
+                b'\n' | b'\r' => {
+                    self.attributes = 0;
+                    handle(Event::LineEnd(input[i]));
+                }
+                b'\x1c' => {
+                    self.attributes = 0;
+                    handle(Event::Reset);
+                }
+                c => self.pending.push(c),
+            }
+            i += 1;
+        }
+    }
+
+    /// Handle the pending bytes if they form a complete or invalid code.
+    fn decode(&mut self, eof: bool, handle: &mut impl FnMut(Event<'_>)) {
+        let rest = match decode(&self.pending, eof) {
+            Decode::Incomplete => return,
+            Decode::Invalid => {
+                handle(Event::Unknown);
+                self.pending.split_off(1)
+            }
+            Decode::Complete(len, code) => {
+                self.apply(code, handle);
+                self.pending.split_off(len)
+            }
+        };
+        self.pending.clear();
+        // Bytes past the code are ordinary input again.
+        self.parse_inner(&rest, eof, handle);
+    }
+
+    fn apply(&mut self, code: Code, handle: &mut impl FnMut(Event<'_>)) {
+        match code {
+            Code::Color {
+                attributes,
+                fg_color,
+                bg_color,
+            } => {
+                for attribute in Attribute::ALL {
+                    if attributes & attribute.mask() != 0 {
+                        self.set(attribute, true, handle);
+                    }
+                }
+                match fg_color {
+                    Some(Color::Irc(color)) => handle(Event::Color(color, None)),
+                    Some(Color::Xterm(index)) => handle(Event::XtermColor(index, None)),
+                    None => {}
+                }
+                match bg_color {
+                    Some(Color::Irc(color)) => handle(Event::Background(color)),
+                    Some(Color::Xterm(index)) => handle(Event::XtermBackground(index)),
+                    None => {}
+                }
+            }
+            Code::ResetColor => handle(Event::ResetColor),
+            Code::SetAttribute(attribute) => self.set(attribute, true, handle),
+            Code::RemoveAttribute(attribute) => self.set(attribute, false, handle),
+        }
+    }
+
+    fn set(&mut self, attribute: Attribute, on: bool, handle: &mut impl FnMut(Event<'_>)) {
+        if (self.attributes & attribute.mask() != 0) != on {
+            self.attributes ^= attribute.mask();
+            handle(Event::Toggle(attribute));
+        }
+    }
+}
+
+fn decode(code: &[u8], eof: bool) -> Decode {
+    let incomplete = if eof {
+        Decode::Invalid
+    } else {
+        Decode::Incomplete
+    };
+    let Some(&kind) = code.get(1) else {
+        return incomplete;
+    };
+
+    match code[0] {
+        b'\x1a' | b'\x1b' => match attribute(kind) {
+            Some(attribute) if code[0] == b'\x1a' => {
+                Decode::Complete(2, Code::SetAttribute(attribute))
+            }
+            Some(attribute) => Decode::Complete(2, Code::RemoveAttribute(attribute)),
+            None => Decode::Invalid,
+        },
+
+        b'\x19' => match kind {
+            b'\x1c' => Decode::Complete(2, Code::ResetColor),
+            b'F' | b'B' | b'*' => {
+                let (len, attributes, color) = match decode_color(code, 2) {
+                    Some(Ok(color)) => color,
+                    Some(Err(())) => return Decode::Invalid,
+                    None => return incomplete,
+                };
+                let (fg_color, bg_color) = match kind {
+                    b'F' => (Some(color), None),
+                    b'B' => (None, Some(color)),
+                    _ => {
+                        // A background may follow so wait for the next byte.
+                        match code.get(len) {
+                            Some(b',' | b'~') => match decode_color(code, len + 1) {
+                                Some(Ok((len, 0, bg_color))) => {
+                                    return Decode::Complete(
+                                        len,
+                                        Code::Color {
+                                            attributes,
+                                            fg_color: Some(color),
+                                            bg_color: Some(bg_color),
+                                        },
+                                    );
+                                }
+                                None if !eof => return Decode::Incomplete,
+                                // Keep the foreground and print the rest.
+                                _ => {}
+                            },
+                            None if !eof => return Decode::Incomplete,
+                            _ => {}
+                        }
+                        (Some(color), None)
+                    }
+                };
+                Decode::Complete(
+                    len,
+                    Code::Color {
+                        attributes,
+                        fg_color,
+                        bg_color,
+                    },
+                )
+            }
+            _ => Decode::Invalid,
+        },
+
+        _ => unreachable!("{:#x} does not start a code", code[0]),
+    }
+}
+
+/// The longest run of attributes before a color, each of the four and `|` once.
+const MAX_ATTRIBUTES_LEN: usize = 5;
+
+/// Decode the attributes and color starting at `code[start]`.
+///
+/// Returns `None` if more bytes are needed, otherwise the end of the color, the attributes and
+/// the color or `Err` if the bytes are not a color.
+fn decode_color(code: &[u8], start: usize) -> Option<Result<(usize, u8, Color), ()>> {
+    let mut i = start;
+    let mut attributes = 0;
+    while let Some(&c) = code.get(i) {
+        match c {
+            b'|' => {}
+            _ => match attribute(c) {
+                Some(attribute) => attributes |= attribute.mask(),
+                None => break,
+            },
+        }
+        i += 1;
+        // Give up on a longer run rather than hold it back and decode it again for every byte.
+        if i - start > MAX_ATTRIBUTES_LEN {
+            return Some(Err(()));
+        }
+    }
+
+    let extended = code.get(i) == Some(&b'@');
+    if extended {
+        i += 1;
+    }
+    let len = if extended { 5 } else { 2 };
+    let digits = code.get(i..)?;
+    let digits = &digits[..digits.len().min(len)];
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return Some(Err(()));
+    }
+    if digits.len() < len {
+        return None;
+    }
+    let value = digits
+        .iter()
+        .fold(0_u32, |value, c| value * 10 + u32::from(c - b'0'));
+
+    let color = if extended {
+        match u8::try_from(value) {
+            Ok(index) => Color::Xterm(index),
+            Err(_) => return Some(Err(())),
+        }
+    } else {
+        match u8::try_from(value).ok().and_then(basic_color) {
+            Some(color) => Color::Irc(color),
+            None => return Some(Err(())),
+        }
+    };
+    Some(Ok((i + len, attributes, color)))
+}
+
+fn attribute(c: u8) -> Option<Attribute> {
+    match c {
+        b'*' => Some(Attribute::Bold),
+        b'!' => Some(Attribute::Reverse),
+        b'/' => Some(Attribute::Italic),
+        b'_' => Some(Attribute::Underline),
+        _ => None,
+    }
+}
+
+/// Map one of WeeChat's basic colors to the closest IRC color.
+fn basic_color(color: u8) -> Option<u8> {
+    Some(match color {
+        0 => 99,  // default
+        1 => 1,   // black
+        2 => 14,  // darkgray
+        3 => 5,   // red
+        4 => 4,   // lightred
+        5 => 3,   // green
+        6 => 9,   // lightgreen
+        7 => 7,   // brown
+        8 => 8,   // yellow
+        9 => 2,   // blue
+        10 => 12, // lightblue
+        11 => 6,  // magenta
+        12 => 13, // lightmagenta
+        13 => 10, // cyan
+        14 => 11, // lightcyan
+        15 => 15, // gray
+        16 => 0,  // white
+        _ => return None,
+    })
+}
+
+fn is_special(c: u8) -> bool {
+    matches!(c, b'\x19' | b'\x1a' | b'\x1b' | b'\x1c' | b'\n' | b'\r')
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+    use std::io::Result;
+
+    use crate::ColorMode;
+    use crate::InputFormat;
+    use crate::IrcatBuilder;
+
+    macro_rules! tests {
+        ($(($name: ident, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let builder = IrcatBuilder::default().input(InputFormat::WeeChat);
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (none, b"foo bar", b"foo bar"),
+        (irc_ignored, b"\x034red\x02", b"\x034red\x02"),
+        (fg, b"\x19F03red\n", b"\x1b[38;5;52mred\x1b[39m\n"),
+        (bg, b"\x19B09blue\n", b"\x1b[44mblue\x1b[49m\n"),
+        (
+            both,
+            b"\x19*04,09text",
+            b"\x1b[31m\x1b[44mtext\x1b[39m\x1b[49m"
+        ),
+        (
+            both_tilde,
+            b"\x19*04~09text",
+            b"\x1b[31m\x1b[44mtext\x1b[39m\x1b[49m"
+        ),
+        (fg_only, b"\x19*04text", b"\x1b[31mtext\x1b[39m"),
+        (fg_comma, b"\x19*04,text", b"\x1b[31m,text\x1b[39m"),
+        (fg_at_end, b"\x19*04", b"\x1b[31m\x1b[39m"),
+        (default, b"\x19F00text", b"\x1b[39mtext"),
+        (
+            extended,
+            b"\x19F@00196red\x19B@00021blue",
+            b"\x1b[38;5;196mred\x1b[48;5;21mblue\x1b[39m\x1b[49m"
+        ),
+        (
+            color_attributes,
+            b"\x19F*_04text",
+            b"\x1b[1m\x1b[4m\x1b[31mtext\x1b[39m\x1b[22m\x1b[24m"
+        ),
+        (keep_attributes, b"\x19F|04text", b"\x1b[31mtext\x1b[39m"),
+        (
+            reset_color,
+            b"\x19F04red\x19\x1cplain",
+            b"\x1b[31mred\x1b[39mplain"
+        ),
+        (
+            attributes,
+            b"\x1a*bold\x1a*still\x1b*plain\x1b*",
+            b"\x1b[1mboldstill\x1b[22mplain"
+        ),
+        (
+            reset,
+            b"\x1a/\x19F04text\x1cplain",
+            b"\x1b[3m\x1b[31mtext\x1b[0mplain"
+        ),
+        (unknown_color, b"\x19F17text", b"F17text"),
+        (option_color, b"\x1905text", b"05text"),
+        (unknown_attribute, b"\x1a#text", b"#text"),
+        (nested, b"\x19F\x1a*text", b"F\x1b[1mtext\x1b[22m"),
+        (truncated, b"text\x19F0", b"textF0"),
+        (
+            all_attributes,
+            b"\x19F*!/_|04text",
+            b"\x1b[1m\x1b[3m\x1b[4m\x1b[7m\x1b[31mtext\x1b[39m\x1b[22m\x1b[23m\x1b[24m\x1b[27m"
+        ),
+        (too_many_attributes, b"\x19F||||||04text", b"F||||||04text"),
+    );
+
+    #[test]
+    fn extended_color_modes() -> Result<()> {
+        for (color_mode, expected) in [
+            (ColorMode::Ansi16, b"\x1b[33morange\x1b[39m".as_ref()),
+            (ColorMode::Ansi256, b"\x1b[38;5;214morange\x1b[39m"),
+            (ColorMode::TrueColor, b"\x1b[38;5;214morange\x1b[39m"),
+        ] {
+            let mut result = Vec::new();
+            IrcatBuilder::default()
+                .input(InputFormat::WeeChat)
+                .color_mode(color_mode)
+                .run(
+                    &mut BufReader::new(b"\x19F@00214orange".as_ref()),
+                    &mut result,
+                )?;
+            assert_eq!(result, expected, "{color_mode:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn long_attributes() -> Result<()> {
+        let mut input = b"\x19F".to_vec();
+        input.resize(input.len() + 100_000, b'|');
+        input.extend_from_slice(b"04text");
+        for capacity in [1, 8192] {
+            let mut result = Vec::new();
+            IrcatBuilder::default().input(InputFormat::WeeChat).run(
+                &mut BufReader::with_capacity(capacity, input.as_slice()),
+                &mut result,
+            )?;
+            assert_eq!(result, input[1..], "capacity {capacity}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use alloc::vec::Vec;
+
+/// Columns taken by characters other than the usual one, as `(first, last, width)`.
+///
+/// This covers the common combining marks and zero width characters, and the East Asian wide and
+/// emoji ranges, rather than every assignment of the Unicode standard.
+#[rustfmt::skip]
+const WIDTHS: [(u32, u32, usize); 83] = [
+    (0x0300, 0x036f, 0), (0x0483, 0x0489, 0), (0x0591, 0x05bd, 0), (0x05bf, 0x05bf, 0),
+    (0x05c1, 0x05c2, 0), (0x05c4, 0x05c5, 0), (0x05c7, 0x05c7, 0), (0x0610, 0x061a, 0),
+    (0x064b, 0x065f, 0), (0x0670, 0x0670, 0), (0x06d6, 0x06dc, 0), (0x06df, 0x06e4, 0),
+    (0x06e7, 0x06e8, 0), (0x06ea, 0x06ed, 0), (0x0e31, 0x0e31, 0), (0x0e34, 0x0e3a, 0),
+    (0x0e47, 0x0e4e, 0), (0x1100, 0x115f, 2), (0x1ab0, 0x1aff, 0), (0x1dc0, 0x1dff, 0),
+    (0x200b, 0x200f, 0), (0x202a, 0x202e, 0), (0x2060, 0x2064, 0), (0x20d0, 0x20ff, 0),
+    (0x231a, 0x231b, 2), (0x2329, 0x232a, 2), (0x23e9, 0x23ec, 2), (0x23f0, 0x23f0, 2),
+    (0x23f3, 0x23f3, 2), (0x25fd, 0x25fe, 2), (0x2614, 0x2615, 2), (0x2648, 0x2653, 2),
+    (0x267f, 0x267f, 2), (0x2693, 0x2693, 2), (0x26a1, 0x26a1, 2), (0x26aa, 0x26ab, 2),
+    (0x26bd, 0x26be, 2), (0x26c4, 0x26c5, 2), (0x26ce, 0x26ce, 2), (0x26d4, 0x26d4, 2),
+    (0x26ea, 0x26ea, 2), (0x26f2, 0x26f3, 2), (0x26f5, 0x26f5, 2), (0x26fa, 0x26fa, 2),
+    (0x26fd, 0x26fd, 2), (0x2705, 0x2705, 2), (0x270a, 0x270b, 2), (0x2728, 0x2728, 2),
+    (0x274c, 0x274c, 2), (0x274e, 0x274e, 2), (0x2753, 0x2755, 2), (0x2757, 0x2757, 2),
+    (0x2795, 0x2797, 2), (0x27b0, 0x27b0, 2), (0x27bf, 0x27bf, 2), (0x2b1b, 0x2b1c, 2),
+    (0x2b50, 0x2b50, 2), (0x2b55, 0x2b55, 2), (0x2e80, 0x303e, 2), (0x3041, 0x33ff, 2),
+    (0x3400, 0x4dbf, 2), (0x4e00, 0x9fff, 2), (0xa000, 0xa4cf, 2), (0xa960, 0xa97f, 2),
+    (0xac00, 0xd7a3, 2), (0xf900, 0xfaff, 2), (0xfe00, 0xfe0f, 0), (0xfe10, 0xfe19, 2),
+    (0xfe20, 0xfe2f, 0), (0xfe30, 0xfe6f, 2), (0xfeff, 0xfeff, 0), (0xff00, 0xff60, 2),
+    (0xffe0, 0xffe6, 2), (0x1_6fe0, 0x1_6fe4, 2), (0x1_7000, 0x1_8aff, 2), (0x1_b000, 0x1_b2ff, 2),
+    (0x1_f300, 0x1_f64f, 2), (0x1_f680, 0x1_f6ff, 2), (0x1_f90c, 0x1_f9ff, 2), (0x1_fa70, 0x1_faff, 2),
+    (0x2_0000, 0x2_fffd, 2), (0x3_0000, 0x3_fffd, 2), (0xe_0100, 0xe_01ef, 0),
+];
+
+/// The number of columns `c` takes in a terminal.
+fn char_width(c: char) -> usize {
+    let c = u32::from(c);
+    match WIDTHS.binary_search_by(|&(first, last, _)| {
+        if last < c {
+            core::cmp::Ordering::Less
+        } else if first > c {
+            core::cmp::Ordering::Greater
+        } else {
+            core::cmp::Ordering::Equal
+        }
+    }) {
+        Ok(i) => WIDTHS[i].2,
+        Err(_) => 1,
+    }
+}
+
+/// The start and width of each character of `text`.
+///
+/// Each byte of invalid UTF-8 counts as a character one column wide.
+pub(crate) fn widths(text: &[u8]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut offset = 0;
+    text.utf8_chunks().flat_map(move |chunk| {
+        let start = offset;
+        let invalid_start = start + chunk.valid().len();
+        offset = invalid_start + chunk.invalid().len();
+        let valid = chunk
+            .valid()
+            .char_indices()
+            .map(move |(i, c)| (start + i, char_width(c)));
+        let invalid = (invalid_start..offset).map(|i| (i, 1));
+        valid.chain(invalid)
+    })
+}
+
+/// The number of columns `text` takes.
+pub(crate) fn width(text: &[u8]) -> usize {
+    widths(text).map(|(_, width)| width).sum()
+}
+
+/// The length of the UTF-8 sequence `c` starts or one if it starts none.
+fn sequence_len(c: u8) -> usize {
+    match c {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => 1,
+    }
+}
+
+fn is_continuation(c: u8) -> bool {
+    matches!(c, 0x80..=0xbf)
+}
+
+/// Holds back a character split across texts so that each piece handed on has whole characters.
+#[derive(Debug, Default)]
+pub(crate) struct Whole {
+    held: Vec<u8>,
+}
+
+impl Whole {
+    /// Call `handle` with `text` and any character held back from before, holding back a character
+    /// left incomplete at the end.
+    pub(crate) fn process(&mut self, mut text: &[u8], mut handle: impl FnMut(&[u8])) {
+        if !self.held.is_empty() {
+            let missing = sequence_len(self.held[0]) - self.held.len();
+            let len = text
+                .iter()
+                .take(missing)
+                .take_while(|c| is_continuation(**c))
+                .count();
+            self.held.extend_from_slice(&text[..len]);
+            text = &text[len..];
+            if len == missing || !text.is_empty() {
+                // The character is complete or never will be.
+                handle(&self.held);
+                self.held.clear();
+            }
+        }
+
+        // Only the last four bytes can hold an incomplete character.
+        let len = text
+            .iter()
+            .enumerate()
+            .rev()
+            .take(4)
+            .find(|(_, c)| !is_continuation(**c))
+            .filter(|(i, c)| sequence_len(**c) > text.len() - i)
+            .map_or(text.len(), |(i, _)| i);
+        if len != 0 {
+            handle(&text[..len]);
+        }
+        self.held.extend_from_slice(&text[len..]);
+    }
+
+    /// Call `handle` with any character held back.
+    pub(crate) fn finish(&mut self, mut handle: impl FnMut(&[u8])) {
+        if !self.held.is_empty() {
+            handle(&self.held);
+            self.held.clear();
+        }
+    }
+}
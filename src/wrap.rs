@@ -0,0 +1,294 @@
+use core::mem;
+
+use crate::parser::Attribute;
+use crate::parser::Event;
+use crate::width::widths;
+use crate::width::Whole;
+
+#[derive(Clone, Copy, Debug)]
+enum Color {
+    Irc(u8),
+    Rgb(u32),
+    Xterm(u8),
+}
+
+impl Color {
+    /// The color for IRC color `index` or `None` for the default color.
+    fn irc(index: u8) -> Option<Self> {
+        (index != 99).then_some(Self::Irc(index))
+    }
+}
+
+/// The formatting active on the current line.
+#[derive(Clone, Copy, Debug, Default)]
+struct Format {
+    fg_color: Option<Color>,
+    bg_color: Option<Color>,
+    attributes: u8,
+    monospace: bool,
+}
+
+/// Breaks lines longer than a fixed number of columns.
+///
+/// Columns are counted in display width like [`Tabs`](crate::tabs::Tabs) does, and a wide
+/// character that does not fit moves to the next line whole. The formatting active at a break is
+/// set again after it since a line ending resets it.
+#[derive(Debug)]
+pub(crate) struct Wrap {
+    width: usize,
+    /// The width of the tab stops tabs are expanded to after wrapping or zero if they are not.
+    tab_width: usize,
+    column: usize,
+    format: Format,
+    /// A character split across texts, held back until it is whole.
+    whole: Whole,
+}
+
+impl Wrap {
+    pub(crate) fn new(width: usize, tab_width: usize) -> Self {
+        Self {
+            width,
+            tab_width,
+            column: 0,
+            format: Format::default(),
+            whole: Whole::default(),
+        }
+    }
+
+    /// Return to the start of a line discarding anything held back.
+    pub(crate) fn reset(&mut self) {
+        *self = Self::new(self.width, self.tab_width);
+    }
+
+    pub(crate) fn process(&mut self, event: Event<'_>, handle: &mut impl FnMut(Event<'_>)) {
+        if let Event::Text(text) = event {
+            let mut whole = mem::take(&mut self.whole);
+            whole.process(text, |text| self.text(text, handle));
+            self.whole = whole;
+            return;
+        }
+        self.finish(handle);
+        match event {
+            Event::LineEnd(_) => {
+                self.column = 0;
+                self.format = Format::default();
+            }
+            Event::Toggle(attribute) => self.format.attributes ^= attribute.mask(),
+            Event::Monospace => self.format.monospace = !self.format.monospace,
+            Event::Color(fg_color, bg_color) => {
+                self.format.fg_color = Color::irc(fg_color);
+                if let Some(bg_color) = bg_color {
+                    self.format.bg_color = Color::irc(bg_color);
+                }
+            }
+            Event::HexColor(fg_color, bg_color) => {
+                self.format.fg_color = Some(Color::Rgb(fg_color));
+                if let Some(bg_color) = bg_color {
+                    self.format.bg_color = Some(Color::Rgb(bg_color));
+                }
+            }
+            Event::Background(bg_color) => self.format.bg_color = Color::irc(bg_color),
+            Event::HexBackground(bg_color) => self.format.bg_color = Some(Color::Rgb(bg_color)),
+            Event::XtermColor(fg_color, bg_color) => {
+                self.format.fg_color = Some(Color::Xterm(fg_color));
+                if let Some(bg_color) = bg_color {
+                    self.format.bg_color = Some(Color::Xterm(bg_color));
+                }
+            }
+            Event::XtermBackground(bg_color) => self.format.bg_color = Some(Color::Xterm(bg_color)),
+            Event::ResetColor => {
+                self.format.fg_color = None;
+                self.format.bg_color = None;
+            }
+            Event::Reset => self.format = Format::default(),
+            Event::Text(_) | Event::Unknown | Event::Ansi(_) => {}
+        }
+        handle(event);
+    }
+
+    /// Emit any character held back.
+    pub(crate) fn finish(&mut self, handle: &mut impl FnMut(Event<'_>)) {
+        let mut whole = mem::take(&mut self.whole);
+        whole.finish(|text| self.text(text, handle));
+        self.whole = whole;
+    }
+
+    fn text(&mut self, text: &[u8], handle: &mut impl FnMut(Event<'_>)) {
+        let mut start = 0;
+        for (i, width) in widths(text) {
+            // A tab reaches the next tab stop from wherever its line starts.
+            let tab = text[i] == b'\t' && self.tab_width != 0;
+            let mut width = if tab {
+                self.tab_width - self.column % self.tab_width
+            } else {
+                width
+            };
+            // Combining marks stay with the character before them.
+            if width != 0 && self.column != 0 && self.column + width > self.width {
+                if start != i {
+                    handle(Event::Text(&text[start..i]));
+                }
+                self.break_line(handle);
+                start = i;
+                if tab {
+                    width = self.tab_width;
+                }
+            }
+            self.column += width;
+        }
+        if start != text.len() {
+            handle(Event::Text(&text[start..]));
+        }
+    }
+
+    /// End the line and set the formatting again on the next.
+    fn break_line(&mut self, handle: &mut impl FnMut(Event<'_>)) {
+        let Format {
+            fg_color,
+            bg_color,
+            attributes,
+            monospace,
+        } = self.format;
+        handle(Event::LineEnd(b'\n'));
+        self.column = 0;
+        match fg_color {
+            Some(Color::Irc(fg_color)) => handle(Event::Color(fg_color, None)),
+            Some(Color::Rgb(fg_color)) => handle(Event::HexColor(fg_color, None)),
+            Some(Color::Xterm(fg_color)) => handle(Event::XtermColor(fg_color, None)),
+            None => {}
+        }
+        match bg_color {
+            Some(Color::Irc(bg_color)) => handle(Event::Background(bg_color)),
+            Some(Color::Rgb(bg_color)) => handle(Event::HexBackground(bg_color)),
+            Some(Color::Xterm(bg_color)) => handle(Event::XtermBackground(bg_color)),
+            None => {}
+        }
+        for attribute in Attribute::ALL {
+            if attributes & attribute.mask() != 0 {
+                handle(Event::Toggle(attribute));
+            }
+        }
+        if monospace {
+            handle(Event::Monospace);
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::io::BufReader;
+    use std::io::Result;
+
+    use crate::IrcatBuilder;
+    use crate::OutputFormat;
+
+    macro_rules! tests {
+        ($(($name: ident, $width: expr, $input: expr, $expected: expr),)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let builder = IrcatBuilder::default().wrap($width);
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::new($input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected);
+
+                    let mut result = Vec::new();
+                    builder.run(&mut BufReader::with_capacity(1, $input.as_ref()), &mut result).unwrap();
+                    assert_eq!(result, $expected, "one byte at a time");
+                }
+            )*
+        }
+    }
+
+    tests!(
+        (off, 0, b"abcdefgh", b"abcdefgh"),
+        (short, 4, b"abc", b"abc"),
+        (exact, 4, b"abcd\nefgh", b"abcd\nefgh"),
+        (long, 4, b"abcdefghij\nk", b"abcd\nefgh\nij\nk"),
+        (
+            colors,
+            4,
+            b"ab\x034,2cdef\x02gh\x03ij",
+            b"ab\x1b[31m\x1b[44mcd\x1b[39m\x1b[49m\n\x1b[31m\x1b[44mef\x1b[1mgh\x1b[39m\x1b[49m\
+              \x1b[22m\n\x1b[1mij\x1b[22m"
+        ),
+        (
+            hex,
+            3,
+            b"\x04ff0000,0000ffabcd\x0399,4e",
+            b"\x1b[38;2;255;0;0m\x1b[48;2;0;0;255mabc\x1b[39m\x1b[49m\n\x1b[38;2;255;0;0m\
+              \x1b[48;2;0;0;255md\x1b[39m\x1b[41me\x1b[49m"
+        ),
+        (
+            default_color,
+            2,
+            b"\x0399,4abc",
+            b"\x1b[39m\x1b[41mab\x1b[49m\n\x1b[41mc\x1b[49m"
+        ),
+        (
+            reset,
+            2,
+            b"\x02\x034ab\x0fcd",
+            b"\x1b[1m\x1b[31mab\x1b[0m\ncd"
+        ),
+        (utf8, 2, "éàü".as_bytes(), "éà\nü".as_bytes()),
+        (
+            wide,
+            5,
+            "日本語です".as_bytes(),
+            "日本\n語で\nす".as_bytes()
+        ),
+        (wide_exact, 4, "a日本b".as_bytes(), "a日\n本b".as_bytes()),
+        (
+            combining,
+            2,
+            "e\u{301}a\u{300}\u{308}o\u{301}".as_bytes(),
+            "e\u{301}a\u{300}\u{308}\no\u{301}".as_bytes()
+        ),
+        (invalid, 2, b"\xe6\x97ab", b"\xe6\x97\nab"),
+        (
+            split_by_code,
+            2,
+            b"a\xe6\x02\x97b",
+            b"a\xe6\x1b[1m\x1b[22m\n\x1b[1m\x97b\x1b[22m"
+        ),
+        (carriage_return, 2, b"abc\rde", b"ab\nc\rde"),
+    );
+
+    #[test]
+    fn html() -> Result<()> {
+        let mut result = Vec::new();
+        IrcatBuilder::default()
+            .wrap(2)
+            .output(OutputFormat::Html)
+            .run(&mut BufReader::new(b"\x034abc".as_ref()), &mut result)?;
+        assert_eq!(
+            result,
+            b"<span style=\"color:#ff0000\">ab</span>\n<span style=\"color:#ff0000\">c</span>"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tabs() -> Result<()> {
+        let mut result = Vec::new();
+        IrcatBuilder::default().wrap(6).expand_tabs(4).run(
+            &mut BufReader::new(b"abcdefg\tx\nabcde\tx".as_ref()),
+            &mut result,
+        )?;
+        assert_eq!(result, b"abcdef\ng   x\nabcde\n    x");
+        Ok(())
+    }
+
+    #[test]
+    fn timestamps() -> Result<()> {
+        let mut result = Vec::new();
+        IrcatBuilder::default()
+            .wrap(6)
+            .timestamp(|output| output.extend_from_slice(b"[T] "))
+            .run(&mut BufReader::new(b"abcdefgh\nij\n".as_ref()), &mut result)?;
+        assert_eq!(result, b"[T] ab\ncdefgh\n[T] ij\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Lets `write!` append to a `Vec<u8>` without `std::io`.
+pub(crate) struct VecWriter<'a>(pub(crate) &'a mut Vec<u8>);
+
+impl fmt::Write for VecWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}